@@ -18,6 +18,9 @@ pub enum InteractionType {
 	Fuel,
 	Fertilize,
 	BuildSaw,
+	Mine,
+	Fish,
+	Weed,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -26,6 +29,7 @@ pub enum CraftType {
 	Water,
 	GardeningTable,
 	SawTable,
+	Cooking,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -51,6 +55,8 @@ pub enum Action {
 	BuildClaim(Structure),
 	Craft(CraftType, Item, HashMap<Item, usize>),
 	Build(Structure, HashMap<Item, usize>),
+	Plant(Structure),
+	Dig,
 }
 
 impl Action{
@@ -71,6 +77,7 @@ impl Action{
 #[derive(Debug, Clone)]
 pub struct Interactable {
 	remains: Option<Structure>,
+	remains_ground: Option<Ground>,
 	items: Vec<Item>,
 	action_type: InteractionType,
 	min_level: u32,
@@ -84,10 +91,18 @@ impl Interactable {
 			min_level,
 			level_odds: level_odds.to_vec(),
 			remains,
+			remains_ground: None,
 			items: items.to_vec()
 		}
 	}
 	
+	pub fn change_ground(action_type: InteractionType, min_level: u32, into: Ground, items: &[Item]) -> Self {
+		Self {
+			remains_ground: Some(into),
+			..Self::new(action_type, min_level, &[], None, items)
+		}
+	}
+	
 	pub fn transform(action_type: InteractionType, min_level: u32, into: Structure) -> Self {
 		Self::new(action_type, min_level, &[], Some(into), &[])
 	}
@@ -100,6 +115,23 @@ impl Interactable {
 		Self::new(InteractionType::Take, 0, &[], Some(Structure::Air), items)
 	}
 	
+	pub fn action_type(&self) -> InteractionType {
+		self.action_type
+	}
+	
+	/// The same interaction, but with the odds of getting the items multiplied by factor
+	pub fn with_odds_factor(self, factor: f32) -> Self {
+		let level_odds = if self.level_odds.is_empty() {
+			vec![factor]
+		} else {
+			self.level_odds.iter().map(|odds| odds * factor).collect()
+		};
+		Self {
+			level_odds,
+			..self
+		}
+	}
+	
 	pub fn apply(&self, action: Interact, time: Tickstamp) -> Option<InteractionResult> {
 		if self.action_type == action.typ && action.level >= self.min_level {
 			let relative_level = (action.level - self.min_level) as usize;
@@ -112,6 +144,7 @@ impl Interactable {
 			};
 			Some(InteractionResult {
 				remains: self.remains,
+				remains_ground: self.remains_ground,
 				items: [
 					action.received(),
 					if odds >= random::random_float(time.random_seed() ^ 84217) {
@@ -0,0 +1,108 @@
+
+use std::collections::BTreeMap;
+use serde::Serialize;
+use crate::{
+	pos::{Pos, Area},
+	tile::{Ground, Structure},
+	tickstamp::Tickstamp,
+	basemap::{BaseMap, InfiniteMap, Biome},
+	worldgen::WorldGenSettings,
+};
+
+const BLOCK_SIZE: i32 = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all="snake_case")]
+pub enum Resource {
+	Tree,
+	Stone,
+	Reed,
+	PitcherPlant,
+}
+
+impl Resource {
+	fn of(structure: Structure) -> Option<Self> {
+		match structure {
+			Structure::Sapling | Structure::YoungTree | Structure::Tree | Structure::OldTree | Structure::OldTreeTinder | Structure::PineTree => Some(Self::Tree),
+			Structure::Stone | Structure::Pebble | Structure::Gravel => Some(Self::Stone),
+			Structure::Reed => Some(Self::Reed),
+			Structure::PitcherPlant => Some(Self::PitcherPlant),
+			_ => None
+		}
+	}
+}
+
+/// Statistics about the generated land around the spawn of a seed
+#[derive(Debug, Clone, Serialize)]
+pub struct SeedAnalysis {
+	pub seed: u32,
+	pub radius: i32,
+	pub spawn: Pos,
+	pub tiles: usize,
+	/// The fraction of tiles in each biome
+	pub biomes: BTreeMap<Biome, f32>,
+	/// The distance from the spawn to the closest tile of each biome that was found
+	pub biome_distance: BTreeMap<Biome, i32>,
+	/// The number of each resource per 1000 tiles
+	pub resources: BTreeMap<Resource, f32>,
+	/// The fraction of tiles that are covered by water
+	pub water: f32,
+}
+
+impl SeedAnalysis {
+	/// Scan all tiles within radius (in both directions) of the spawn
+	pub fn new(seed: u32, radius: i32, settings: &WorldGenSettings) -> Self {
+		let mut map = InfiniteMap::with_settings(seed, settings.clone());
+		let spawn = map.player_spawn();
+		let area = Area::centered(spawn, Pos::new(radius * 2 + 1, radius * 2 + 1));
+		let mut biome_tiles: BTreeMap<Biome, usize> = BTreeMap::new();
+		let mut biome_distance: BTreeMap<Biome, i32> = BTreeMap::new();
+		let mut resource_tiles: BTreeMap<Resource, usize> = BTreeMap::new();
+		let mut water_tiles = 0;
+		for block in area.blocks(Pos::new(BLOCK_SIZE, BLOCK_SIZE)) {
+			map.prepare(block);
+			for (pos, tile) in map.region(block, Tickstamp(0)) {
+				let biome = map.tile_biome(pos);
+				*biome_tiles.entry(biome).or_default() += 1;
+				let distance = biome_distance.entry(biome).or_insert(i32::MAX);
+				*distance = (*distance).min(pos.distance_to(spawn));
+				if let Some(resource) = Resource::of(tile.structure) {
+					*resource_tiles.entry(resource).or_default() += 1;
+				}
+				if matches!(tile.ground, Ground::Water | Ground::BogWater | Ground::WetChannel) {
+					water_tiles += 1;
+				}
+			}
+		}
+		let tiles = area.surface() as usize;
+		Self {
+			seed,
+			radius,
+			spawn,
+			tiles,
+			biomes: biome_tiles.into_iter()
+				.map(|(biome, n)| (biome, n as f32 / tiles as f32))
+				.collect(),
+			biome_distance,
+			resources: resource_tiles.into_iter()
+				.map(|(resource, n)| (resource, n as f32 * 1000.0 / tiles as f32))
+				.collect(),
+			water: water_tiles as f32 / tiles as f32,
+		}
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn spawn_is_in_start_biome() {
+		let analysis = SeedAnalysis::new(9876, 40, &WorldGenSettings::default());
+		assert_eq!(analysis.tiles, 81 * 81);
+		assert_eq!(analysis.biome_distance[&Biome::Start], 0);
+		let total: f32 = analysis.biomes.values().sum();
+		assert!((total - 1.0).abs() < 0.001);
+	}
+}
@@ -1,13 +1,23 @@
 
 
+use std::collections::{HashMap, BTreeMap};
+use enum_dispatch::enum_dispatch;
+use enum_assoc::Assoc;
+use serde::{Serialize, Deserialize};
 use crate::{
-	pos::{Pos, Area},
+	pos::{Pos, Area, Layer},
 	tickstamp::Tickstamp,
 	tile::{Tile, Ground, Structure},
 	random::{WhiteNoise, randomize_u32, pick, pick_weighted},
 	randomtick,
-	util::math,
-	heightmap::{HeightMap, LazyHeightMap}
+	util::{math, parallel},
+	heightmap::{HeightMap, LazyHeightMap, CachedHeightMap},
+	grid::Grid,
+	poi::Poi,
+	cavemap::CaveMap,
+	fixedmap::{FixedMap, FixedMapTemplate},
+	legacymap::LegacyMap,
+	worldgen::WorldGenSettings,
 };
 
 macro_rules! t {
@@ -17,45 +27,167 @@ macro_rules! t {
 	($g:expr, $s:expr) => {Tile::structure($g, $s)};
 }
 
-const BIOME_SIZE: i32 = 48;
-const EDGE_SIZE: i32 = BIOME_SIZE / 4;
+const RIVER_WIDTH: f32 = 2.0;
+const RIVER_BANK: f32 = 1.5;
+const RIVER_MEANDER: f32 = 10.0;
+/// Lakes that are two biomes apart can be connected. The opposite directions are covered from the other lake.
+const RIVER_OFFSETS: [(i32, i32); 6] = [(2, 0), (0, 2), (-2, 2), (1, 1), (-1, 2), (2, -1)];
+/// How many biomes away to look for the nearest biome of each type
+const COMPASS_RANGE: i32 = 6;
+/// How many biome cells InfiniteMap keeps biomes and rivers for. Beyond that, the least recently used half is forgotten
+const MAX_CACHED_BIOMES: usize = 4096;
 
+#[enum_dispatch]
 pub trait BaseMap {
 
 	fn cell(&mut self, pos: Pos, time: Tickstamp) -> Tile;
 	
-	fn region(&mut self, area: Area, time: Tickstamp) -> Vec<(Pos, Tile)> {
-		area.iter().map(|pos| (pos, self.cell(pos, time))).collect()
-	}
+	/// Fill the caches that region uses for area. Regions of areas that were not prepared are the same, only slower to generate
+	fn prepare(&mut self, _area: Area) {}
+	
+	/// The tiles of area in row order. This doesn't change the map, so regions of several areas can be generated at once
+	fn region(&self, area: Area, time: Tickstamp) -> Vec<(Pos, Tile)>;
 	
 	fn player_spawn(&mut self) -> Pos;
+
+	/// How the tile at pos was generated, for maps that have biomes
+	fn meta(&mut self, _pos: Pos) -> Option<TileMeta> {
+		None
+	}
+
+	/// The core of the nearest biome of each type around pos
+	fn nearest_biomes(&mut self, _pos: Pos) -> Vec<(Biome, Pos)> {
+		Vec::new()
+	}
+}
+
+/// What the base map generated a tile from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileMeta {
+	pub biome: Biome,
+	/// The core of the biome cell of the tile. All tiles of a biome cell have the same core
+	pub core: Pos,
+	/// How far the tile is from the nearest biome of another type
+	pub edge_distance: i32,
+}
+
+#[enum_dispatch(BaseMap)]
+pub enum BaseMapEnum {
+	InfiniteMap,
+	CaveMap,
+	FixedMap,
+	LegacyMap,
+}
+
+/// What kind of base map a world is built on. Chosen when the world is created
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all="lowercase")]
+pub enum WorldType {
+	#[default]
+	Infinite,
+	Fixed(FixedMapTemplate),
+}
+
+impl WorldType {
+	pub fn basemap(&self, layer: Layer, seed: u32, settings: &WorldGenSettings, generator: Generator) -> BaseMapEnum {
+		match self {
+			Self::Infinite => generator.basemap(layer, seed, settings),
+			Self::Fixed(template) if layer == Layer::SURFACE => FixedMap::new(template).into(),
+			// fixed maps have no caves
+			Self::Fixed(_) => FixedMap::filled(t!(RockFloor, RockMid)).into(),
+		}
+	}
+}
+
+/// The version of the generator for infinite maps.
+/// Worlds keep generating terrain with the version they were created with, so changes to the generator don't reshape existing worlds.
+/// When the output of the generator changes, the old generator is kept as its own BaseMap and gets a new version here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all="lowercase")]
+pub enum Generator {
+	/// Also used for worlds saved before generator versions existed
+	#[default]
+	V0,
+	/// Climate biomes, rivers, points of interest and caves
+	V1,
+}
+
+impl Generator {
+	pub const LATEST: Self = Self::V1;
+
+	fn basemap(&self, layer: Layer, seed: u32, settings: &WorldGenSettings) -> BaseMapEnum {
+		match self {
+			Self::V0 if layer == Layer::SURFACE => LegacyMap::new(seed).into(),
+			// the legacy surface has no cave entrances
+			Self::V0 => FixedMap::filled(t!(RockFloor, RockMid)).into(),
+			Self::V1 if layer == Layer::SURFACE => InfiniteMap::with_settings(seed, settings.clone()).into(),
+			Self::V1 => CaveMap::new(seed, settings.clone()).into(),
+		}
+	}
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Copy)]
-enum Biome {
+#[derive(Debug, Clone, PartialEq, Eq, Copy, PartialOrd, Ord, Serialize, Deserialize, Assoc)]
+#[serde(rename_all="lowercase")]
+#[func(pub fn name(&self) -> &str)]
+pub enum Biome {
+	#[assoc(name="starting area")]
 	Start,
+	#[assoc(name="forest")]
 	Forest,
+	#[assoc(name="field")]
 	Field,
+	#[assoc(name="lake")]
 	Lake,
+	#[assoc(name="rock formation")]
 	Rocks,
-	Bog
+	#[assoc(name="bog")]
+	Bog,
+	#[assoc(name="desert")]
+	Desert,
+	#[assoc(name="highland")]
+	Highland,
+	#[assoc(name="snowfield")]
+	Snowfield
 }
 
 pub struct InfiniteMap {
 	seed: u32,
-	heightmaps: HeightMaps<LazyHeightMap>
+	heightmaps: HeightMaps<LazyHeightMap>,
+	temperature: LazyHeightMap,
+	moisture: LazyHeightMap,
+	biomes: HashMap<BPos, Biome>,
+	rivers: HashMap<BPos, Vec<River>>,
+	/// When each cached biome cell was last used, counted in calls to cache_biomes
+	last_used: HashMap<BPos, u64>,
+	cache_calls: u64,
+	settings: WorldGenSettings,
 }
 
 impl InfiniteMap {
+	#[cfg(test)]
 	pub fn new(seed: u32) -> Self {
+		Self::with_settings(seed, WorldGenSettings::default())
+	}
+
+	pub fn with_settings(seed: u32, settings: WorldGenSettings) -> Self {
+		let octaves = &settings.heightmaps;
 		Self {
 			seed,
 			heightmaps: HeightMaps {
-				lake: LazyHeightMap::new(seed + 344, vec![(3,0.12), (5,0.20), (7,0.26), (11,0.42)]),
-				rock: LazyHeightMap::new(seed + 344, vec![(3,0.12), (5,0.20), (7,0.26), (11,0.42)]),
-				bog: LazyHeightMap::new(seed + 382, vec![(3,0.25), (5,0.35), (7,0.4)]),
-				reed: LazyHeightMap::new(seed+276, vec![(7, 0.5), (11, 0.5)])
+				lake: LazyHeightMap::new(seed + 344, octaves.lake.clone()),
+				rock: LazyHeightMap::new(seed + 344, octaves.rock.clone()),
+				bog: LazyHeightMap::new(seed + 382, octaves.bog.clone()),
+				reed: LazyHeightMap::new(seed+276, octaves.reed.clone()),
+				river: LazyHeightMap::new(seed+1848, octaves.river.clone()),
+				dune: LazyHeightMap::new(seed+913, octaves.dune.clone()),
 			},
+			temperature: LazyHeightMap::new(seed+5261, octaves.temperature.clone()),
+			moisture: LazyHeightMap::new(seed+5873, octaves.moisture.clone()),
+			biomes: HashMap::new(),
+			rivers: HashMap::new(),
+			last_used: HashMap::new(),
+			cache_calls: 0,
+			settings,
 		}
 	}
 	
@@ -68,37 +200,126 @@ impl InfiniteMap {
 		self.biome_core(self.start_biome()) + Pos::new(0, 2)
 	}
 
+	/// The biome that the tile at pos is generated from
+	pub fn tile_biome(&self, pos: Pos) -> Biome {
+		self.biome_at(self.biome_pos(pos).0)
+	}
+
 	fn biome_at(&self, b_pos: BPos) -> Biome {
+		self.biomes.get(&b_pos).copied().unwrap_or_else(|| self.generate_biome(b_pos))
+	}
+	
+	/// Remember the biomes of all biome cells that can influence tiles in area, and the rivers near the biome cells of area.
+	/// Cells that are already known are kept until the cache grows beyond MAX_CACHED_BIOMES
+	pub fn cache_biomes(&mut self, area: Area) {
+		self.cache_calls += 1;
+		let cells = self.base_cells(area);
+		// rivers can come from 3 biomes away
+		for bpos in cells.grow(4).iter().map(BPos) {
+			if !self.biomes.contains_key(&bpos) {
+				let biome = self.generate_biome(bpos);
+				self.biomes.insert(bpos, biome);
+			}
+			self.last_used.insert(bpos, self.cache_calls);
+		}
+		for bpos in cells.grow(1).iter().map(BPos) {
+			self.cache_rivers(bpos);
+		}
+		self.forget_unused();
+	}
+	
+	/// The base biome cells of all positions in area
+	fn base_cells(&self, area: Area) -> Area {
+		let corners = [area.min(), area.max(), Pos::new(area.min().x, area.max().y), Pos::new(area.max().x, area.min().y)]
+			.map(|corner| self.base_bpos(corner).0);
+		let min = corners.into_iter().reduce(|a, b| Pos::new(a.x.min(b.x), a.y.min(b.y))).unwrap();
+		let max = corners.into_iter().reduce(|a, b| Pos::new(a.x.max(b.x), a.y.max(b.y))).unwrap();
+		Area::between(min, max + Pos::new(1, 1))
+	}
+	
+	fn cache_rivers(&mut self, bpos: BPos) {
+		if !self.rivers.contains_key(&bpos) {
+			let rivers = self.rivers_near(bpos);
+			self.rivers.insert(bpos, rivers);
+		}
+		self.last_used.insert(bpos, self.cache_calls);
+	}
+	
+	fn forget_unused(&mut self) {
+		if self.last_used.len() <= MAX_CACHED_BIOMES {
+			return;
+		}
+		let mut uses: Vec<u64> = self.last_used.values().copied().collect();
+		uses.sort_unstable();
+		let oldest_kept = uses[uses.len() - MAX_CACHED_BIOMES / 2];
+		self.last_used.retain(|_, used| *used >= oldest_kept);
+		let last_used = &self.last_used;
+		self.biomes.retain(|bpos, _| last_used.contains_key(bpos));
+		self.rivers.retain(|bpos, _| last_used.contains_key(bpos));
+	}
+	
+	fn generate_biome(&self, b_pos: BPos) -> Biome {
 		if b_pos == self.start_biome() {
-			Biome::Start
+			return Biome::Start;
+		}
+		// climate varies over several biomes so that neighbouring biomes fit together
+		let temperature = self.temperature.height(b_pos.0);
+		let moisture = self.moisture.height(b_pos.0);
+		let rind = WhiteNoise::new(self.seed+333).gen(b_pos.0);
+		let weights = &self.settings.biomes;
+		if temperature < 0.34 {
+			Biome::Snowfield
+		} else if temperature < 0.40 {
+			*pick_weighted(rind, &weights.cold)
+		} else if temperature > 0.62 && moisture < 0.45 {
+			*pick_weighted(rind, &weights.hot)
+		} else if moisture > 0.55 {
+			*pick_weighted(rind, &weights.wet)
 		} else {
-			*pick_weighted(
-				WhiteNoise::new(self.seed+333).gen(b_pos.0),
-				&[
-					(Biome::Field, 10),
-					(Biome::Forest, 10),
-					(Biome::Lake, 5),
-					(Biome::Rocks, 5),
-					(Biome::Bog, 5),
-				]
-			)
+			*pick_weighted(rind, &weights.temperate)
+		}
+	}
+	
+	fn poi_at(&self, bpos: BPos) -> Option<Poi> {
+		let rind = WhiteNoise::new(self.seed + 4751).gen(bpos.0);
+		if rind % 5 != 0 {
+			return None;
 		}
+		let options: &[Poi] = match self.biome_at(bpos) {
+			Biome::Start | Biome::Lake | Biome::Rocks => &[],
+			Biome::Field | Biome::Forest => &[Poi::Ruin, Poi::Garden, Poi::Cache],
+			Biome::Highland | Biome::Desert | Biome::Snowfield => &[Poi::Ruin, Poi::Cache],
+			Biome::Bog => &[Poi::Cache],
+		};
+		options.get((randomize_u32(rind) as usize) % options.len().max(1)).copied()
+	}
+	
+	/// The cave entrances within distance of pos. Each rocks biome has one at its core.
+	pub fn cave_entrances_near(&self, pos: Pos, distance: i32) -> Vec<Pos> {
+		self.neighbour_biomes(pos)
+			.filter(|(d, bpos)| *d <= distance && self.biome_at(*bpos) == Biome::Rocks)
+			.map(|(_, bpos)| self.biome_core(bpos))
+			.collect()
+	}
+	
+	fn base_bpos(&self, pos: Pos) -> BPos {
+		BPos(Pos::new(pos.x - (pos.y / 2), pos.y) / self.settings.biome_size)
 	}
 	
 	fn biome_core(&self, bpos: BPos) -> Pos {
 		let rind = WhiteNoise::new(self.seed+821).gen(bpos.0);
-		let core_size = BIOME_SIZE / 2;
+		let core_size = self.settings.biome_size / 2;
 		let core_offset = if bpos == self.start_biome() {
 			Pos::new(0, 0)
 		} else {
 			Area::centered(Pos::new(0, 0), Pos::new(core_size, core_size))
 				.random_pos(rind)
 		};
-		bpos.0 * BIOME_SIZE + core_offset + Pos::new(bpos.0.y * BIOME_SIZE / 2, 0)
+		bpos.0 * self.settings.biome_size + core_offset + Pos::new(bpos.0.y * self.settings.biome_size / 2, 0)
 	}
 	
 	fn neighbour_biomes(&self, pos: Pos) -> impl Iterator<Item=(i32, BPos)> + '_ {
-		let bpos = BPos(Pos::new(pos.x - (pos.y / 2), pos.y)  / BIOME_SIZE);
+		let bpos = self.base_bpos(pos);
 		[(0, 0), (1, 0), (0, 1), (1, 1), (0, 2), (1, 2)].into_iter()
 			.map(move |p| {
 				let b = BPos(bpos.0 + p);
@@ -123,12 +344,12 @@ impl InfiniteMap {
 		distances[1..].iter()
 			.find(|(_, b)| self.biome_at(*b) != my_biome)
 			.map(|(d, _)| d - dist)
-			.unwrap_or(BIOME_SIZE / 2)
+			.unwrap_or(self.settings.biome_size / 2)
 	}
 
 	fn biome_pos(&self, pos: Pos) -> (BPos, Pos) {
 		let rind = WhiteNoise::new(self.seed+343).gen(pos);
-		let edge_size = EDGE_SIZE;
+		let edge_size = self.settings.edge_size;
 		let mut offset = Pos::new((rind % edge_size as u32) as i32 - edge_size / 2, ((rind / edge_size as u32) % edge_size as u32) as i32 - edge_size / 2);
 		if offset.size() > edge_size / 2 {
 			offset = offset % edge_size - Pos::new(edge_size/2, edge_size/2);
@@ -140,11 +361,96 @@ impl InfiniteMap {
 	}
 
 
-	fn tile(&self, pos: Pos, time: Tickstamp) -> Tile {
+	fn has_river(&self, from: BPos, to: BPos) -> bool {
+		randomize_u32(WhiteNoise::new(self.seed + 1723).gen(from.0) ^ WhiteNoise::new(self.seed + 1724).gen(to.0)) % 3 != 0
+			&& self.biome_at(from) == Biome::Lake
+			&& self.biome_at(to) == Biome::Lake
+	}
+	
+	/// All rivers that can come close to positions in this biome cell
+	fn rivers_near(&self, bpos: BPos) -> Vec<River> {
+		let mut rivers = Vec::new();
+		for offset in RIVER_OFFSETS.map(Pos::from_tuple) {
+			// a river passing near bpos starts at most one biome beyond the biomes between bpos and the other end
+			let from_area = Area::between(
+				bpos.0 + Pos::new((-offset.x).min(0), (-offset.y).min(0)) - Pos::new(1, 1),
+				bpos.0 + Pos::new((-offset.x).max(0), (-offset.y).max(0)) + Pos::new(2, 2)
+			);
+			for from in from_area.iter().map(BPos) {
+				let to = BPos(from.0 + offset);
+				if self.has_river(from, to) {
+					rivers.push(River {from: self.biome_core(from), to: self.biome_core(to)});
+				}
+			}
+		}
+		rivers
+	}
+	
+	/// Distance to the nearest river and whether that part of the river is a ford
+	fn river_distance(&self, pos: Pos, rivers: &[River], heightmaps: &HeightMaps<impl HeightMap>) -> Option<(f32, bool)> {
+		let max_distance = RIVER_WIDTH + RIVER_BANK + RIVER_MEANDER;
+		let mut distance_offset = None;
+		let mut nearest: Option<(f32, bool)> = None;
+		for river in rivers {
+			let (segment_distance, along) = segment_distance(pos, river.from, river.to);
+			if segment_distance >= 2.0 * max_distance {
+				continue;
+			}
+			let distance = segment_distance + *distance_offset.get_or_insert_with(||
+				(heightmaps.river.height(pos) - 0.5) * 2.0 * RIVER_MEANDER
+			);
+			if distance < max_distance && nearest.map_or(true, |(d, _)| distance < d) {
+				let length = river.from.distance_to(river.to) as f32;
+				let ford = ((along - 0.5) * length).abs() < 1.5;
+				nearest = Some((distance, ford));
+			}
+		}
+		nearest
+	}
+	
+	fn river_tile(&self, pos: Pos, biome: Biome, rind: u32, rivers: &[River], heightmaps: &HeightMaps<impl HeightMap>) -> Option<Tile> {
+		let (distance, ford) = self.river_distance(pos, rivers, heightmaps)?;
+		if distance < RIVER_WIDTH {
+			Some(if ford && biome != Biome::Lake {
+				*pick(rind, &[t!(Dirt), t!(Dirt, Reed)])
+			} else {
+				t!(Water)
+			})
+		} else if distance < RIVER_WIDTH + RIVER_BANK && biome != Biome::Lake {
+			Some(*pick_weighted(rind, &[
+				(t!(Dirt, Reed), 3),
+				(t!(Dirt), 1),
+			]))
+		} else {
+			None
+		}
+	}
+
+	/// rock_heights keeps the rock heights that were computed, so the neighbours of rock tiles are only computed once
+	fn tile(&self, pos: Pos, time: Tickstamp, heightmaps: &HeightMaps<impl HeightMap>, rock_heights: &mut Grid<Option<f32>>) -> Tile {
+		let bpos = self.base_bpos(pos);
+		match self.rivers.get(&bpos) {
+			Some(rivers) => self.tile_with_rivers(pos, time, rivers, heightmaps, rock_heights),
+			None => self.tile_with_rivers(pos, time, &self.rivers_near(bpos), heightmaps, rock_heights),
+		}
+	}
+
+	fn tile_with_rivers(&self, pos: Pos, time: Tickstamp, rivers: &[River], heightmaps: &HeightMaps<impl HeightMap>, rock_heights: &mut Grid<Option<f32>>) -> Tile {
 		let (bpos, dpos) = self.biome_pos(pos);
 		let biome = self.biome_at(bpos);
 		let rind = WhiteNoise::new(self.seed + 7943).gen(pos);
 		let rtime = randomtick::tick_num(pos, time) as u32 + WhiteNoise::new(self.seed + 356).gen(pos);
+		if let Some(tile) = self.poi_at(bpos).and_then(|poi| poi.tile(dpos)) {
+			return tile;
+		}
+		if biome == Biome::Rocks && dpos.abs().max() <= 1 {
+			return if dpos.is_zero() { t!(RockFloor, CaveEntrance) } else { t!(RockFloor) };
+		}
+		if biome != Biome::Start {
+			if let Some(tile) = self.river_tile(pos, biome, rind, rivers, heightmaps) {
+				return tile;
+			}
+		}
 		match biome {
 			Biome::Start => {
 				let dspawn = dpos.abs();
@@ -217,9 +523,9 @@ impl InfiniteMap {
 				])
 			}
 			Biome::Lake => {
-				let c = ((self.edge_distance(pos) - EDGE_SIZE) as f32 / 12.0).clamp(0.0, 1.0);
-				let reed_density = self.heightmaps.reed.height(pos) * 0.4 - 0.2;
-				let height = 0.4 - self.heightmaps.lake.height(pos) + (1.0 - c) * 0.6;
+				let c = ((self.edge_distance(pos) - self.settings.edge_size) as f32 / 12.0).clamp(0.0, 1.0);
+				let reed_density = heightmaps.reed.height(pos) * 0.4 - 0.2;
+				let height = 0.4 - heightmaps.lake.height(pos) + (1.0 - c) * 0.6;
 				if height.abs() < reed_density {
 					t!(
 						if height > 0.0 { Ground::Dirt } else { Ground::Water },
@@ -245,11 +551,11 @@ impl InfiniteMap {
 			}
 			Biome::Rocks => {
 				let min_height = 0.6;
-				let height = self.rock_height(pos);
+				let height = self.rock_height_in(pos, heightmaps, rock_heights);
 				if height > min_height {
 					let ismid = [(0, -1), (0, 1), (-1, 0), (1, 0), (-1, -1), (-1, 1), (1, -1), (1, 1)]
 						.into_iter()
-						.all(|d| self.rock_height(pos + d) > min_height);
+						.all(|d| self.rock_height_in(pos + d, heightmaps, rock_heights) > min_height);
 					t!(
 						Ground::RockFloor,
 						if ismid {
@@ -283,9 +589,9 @@ impl InfiniteMap {
 				}
 			}
 			Biome::Bog => {
-				let height = self.heightmaps.bog.height(pos) + WhiteNoise::new(self.seed+3294).gen_f(pos) * 0.1;
+				let height = heightmaps.bog.height(pos) + WhiteNoise::new(self.seed+3294).gen_f(pos) * 0.1;
 				if height < 0.45 {
-					t!(Water)
+					t!(BogWater)
 				} else {
 					*pick_weighted(rind, &[
 						(t!(Grass1), 50),
@@ -303,44 +609,199 @@ impl InfiniteMap {
 					])
 				}
 			}
+			Biome::Desert => {
+				let dune = heightmaps.dune.height(pos);
+				if dune > 0.62 && rind % 3 != 0 {
+					t!(Sand, Gravel)
+				} else {
+					*pick_weighted(rind, &[
+						(t!(Sand), 200),
+						(t!(Sand, Cactus), 4),
+						(t!(Sand, Shrub), 1),
+						(*pick_weighted(rtime, &[
+							(t!(Sand), 10),
+							(t!(Sand, Pebble), 1),
+						]), 4),
+						(t!(RockFloor, Stone), 1),
+					])
+				}
+			}
+			Biome::Highland => {
+				let height = self.rock_height_in(pos, heightmaps, rock_heights);
+				if height > 0.7 {
+					t!(RockFloor, Rock)
+				} else if height > 0.55 {
+					*pick_weighted(rind, &[
+						(t!(RockFloor), 10),
+						(t!(RockFloor, Gravel), 3),
+						(t!(RockFloor, Stone), 1),
+					])
+				} else {
+					*pick_weighted(rind, &[
+						(t!(Grass2), 10),
+						(t!(Grass3), 10),
+						(t!(Grass3, Heather), 25),
+						(t!(Moss), 5),
+						(t!(Grass2, Shrub), 1),
+						(t!(Dirt, Pebble), 1),
+					])
+				}
+			}
+			Biome::Snowfield => {
+				let height = 0.3 - heightmaps.lake.height(pos) + ((self.edge_distance(pos) - self.settings.edge_size) as f32 / 24.0).clamp(0.0, 1.0) * 0.3;
+				if height < 0.0 {
+					t!(Ice)
+				} else {
+					*pick_weighted(rtime, &[
+						(*pick_weighted(rind, &[
+							(t!(Snow), 100),
+							(t!(Snow, Stone), 1),
+							(t!(Snow, Shrub), 2),
+						]), 100),
+						(t!(Snow, PineTree), 8),
+					])
+				}
+			}
 		}
 	}
 	
-	fn rock_height(&self, pos: Pos) -> f32 {
-		let c = ((self.edge_distance(pos) - EDGE_SIZE) as f32 / 4.0).clamp(0.0, 1.0);
-		math::ease_in_out_cubic(self.heightmaps.rock.height(pos)) * c
+	fn rock_height(&self, pos: Pos, heightmaps: &HeightMaps<impl HeightMap>) -> f32 {
+		let c = ((self.edge_distance(pos) - self.settings.edge_size) as f32 / 4.0).clamp(0.0, 1.0);
+		math::ease_in_out_cubic(heightmaps.rock.height(pos)) * c
+	}
+	
+	/// The rock height at pos, remembered in rock_heights when it covers pos
+	fn rock_height_in(&self, pos: Pos, heightmaps: &HeightMaps<impl HeightMap>, rock_heights: &mut Grid<Option<f32>>) -> f32 {
+		if let Some(Some(height)) = rock_heights.get(pos) {
+			return *height;
+		}
+		let height = self.rock_height(pos, heightmaps);
+		rock_heights.set(pos, Some(height));
+		height
 	}
 }
 
 impl BaseMap for InfiniteMap {
 	fn cell(&mut self, pos: Pos, time: Tickstamp) -> Tile {
-		self.tile(pos, time)
+		self.cache_rivers(self.base_bpos(pos));
+		self.forget_unused();
+		self.tile(pos, time, &self.heightmaps, &mut Grid::new(Area::centered(pos, Pos::new(3, 3)), None))
 	}
 	
 
-	fn region(&mut self, area: Area, time: Tickstamp) -> Vec<(Pos, Tile)> {
-		area.iter().map(|pos| (pos, self.tile(pos, time))).collect()
+	fn prepare(&mut self, area: Area) {
+		self.cache_biomes(area.grow(self.settings.edge_size));
+	}
+
+	fn region(&self, area: Area, time: Tickstamp) -> Vec<(Pos, Tile)> {
+		// rock formations look at the neighbours of each tile
+		let heightmaps = self.heightmaps.cache(area.grow(1));
+		parallel::in_bands(area, |band| {
+			let mut rock_heights = Grid::new(band.grow(1), None);
+			band.iter().map(|pos| (pos, self.tile(pos, time, &heightmaps, &mut rock_heights))).collect()
+		})
 	}
 	
 	fn player_spawn(&mut self) -> Pos {
 		self.start_pos()
 	}
+
+	fn meta(&mut self, pos: Pos) -> Option<TileMeta> {
+		let (bpos, _) = self.biome_pos(pos);
+		Some(TileMeta {
+			biome: self.biome_at(bpos),
+			core: self.biome_core(bpos),
+			edge_distance: self.edge_distance(pos),
+		})
+	}
+
+	fn nearest_biomes(&mut self, pos: Pos) -> Vec<(Biome, Pos)> {
+		let mut nearest: BTreeMap<Biome, (i32, Pos)> = BTreeMap::new();
+		let range = Area::centered(self.base_bpos(pos).0, Pos::new(COMPASS_RANGE * 2 + 1, COMPASS_RANGE * 2 + 1));
+		for bpos in range.iter().map(BPos) {
+			let core = self.biome_core(bpos);
+			let distance = pos.distance_to(core);
+			let closest = nearest.entry(self.biome_at(bpos)).or_insert((distance, core));
+			if distance < closest.0 {
+				*closest = (distance, core);
+			}
+		}
+		nearest.into_iter()
+			.map(|(biome, (_, core))| (biome, core))
+			.collect()
+	}
 }
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, Default)]
 struct BPos(Pos);
 
+
+#[derive(Debug, Clone, Copy)]
+struct River {
+	from: Pos,
+	to: Pos
+}
+
+/// Distance from pos to the line segment between a and b, and how far along the segment the closest point is
+fn segment_distance(pos: Pos, a: Pos, b: Pos) -> (f32, f32) {
+	let (px, py) = ((pos.x - a.x) as f32, (pos.y - a.y) as f32);
+	let (dx, dy) = ((b.x - a.x) as f32, (b.y - a.y) as f32);
+	let length_squared = dx * dx + dy * dy;
+	let along = if length_squared == 0.0 {
+		0.0
+	} else {
+		((px * dx + py * dy) / length_squared).clamp(0.0, 1.0)
+	};
+	((px - along * dx).hypot(py - along * dy), along)
+}
+
 struct HeightMaps<T: HeightMap> {
 	lake: T,
 	bog: T,
 	rock: T,
-	reed: T
+	reed: T,
+	river: T,
+	dune: T,
+}
+
+impl HeightMaps<LazyHeightMap> {
+	fn cache(&self, area: Area) -> HeightMaps<CachedHeightMap<'_>> {
+		HeightMaps {
+			lake: self.lake.cache(area),
+			bog: self.bog.cache(area),
+			rock: self.rock.cache(area),
+			reed: self.reed.cache(area),
+			river: self.river.cache(area),
+			dune: self.dune.cache(area),
+		}
+	}
 }
 
 
 #[cfg(test)]
 mod tests {
 	use super::*;
+
+	fn region_hash(basemap: &mut BaseMapEnum, area: Area) -> u64 {
+		basemap.prepare(area);
+		crate::util::fnv1a(&serde_json::to_vec(&basemap.region(area, Tickstamp(0))).unwrap())
+	}
+
+	#[test]
+	fn generator_output_is_unchanged() {
+		// If this fails the terrain of existing worlds would change: keep the old generator under its version and add a new one
+		let golden = [
+			(Generator::V0, Layer::SURFACE, 9876, Area::new(Pos::new(-100, -100), Pos::new(200, 200)), 0x791c6c8eb8251182),
+			(Generator::V0, Layer::SURFACE, 1234, Area::new(Pos::new(1000, -2000), Pos::new(150, 150)), 0xbbeaf484fe0a7d4a),
+			(Generator::V1, Layer::SURFACE, 9876, Area::new(Pos::new(-100, -100), Pos::new(200, 200)), 0x2c6e98b35802f4ec),
+			(Generator::V1, Layer::SURFACE, 1234, Area::new(Pos::new(1000, -2000), Pos::new(150, 150)), 0x84d20561ad72c90b),
+			(Generator::V1, Layer::UNDERGROUND, 9876, Area::new(Pos::new(-100, -100), Pos::new(200, 200)), 0x462fb3daa3c387bc),
+		];
+		for (generator, layer, seed, area, hash) in golden {
+			let mut basemap = generator.basemap(layer, seed, &WorldGenSettings::default());
+			assert_eq!(region_hash(&mut basemap, area), hash, "{:?} on layer {:?} with seed {} in {:?}", generator, layer, seed, area);
+		}
+	}
 	#[test]
 	fn core_is_in_own_biome() {
 		let map = InfiniteMap::new(678);
@@ -374,6 +835,7 @@ mod tests {
 		for cx in -2..=2 {
 			for cy in -2..=2 {
 				let area = Area::centered(Pos::new(221 + cx * 1357 , -873 + cy * 2931), Pos::new(32, 32));
+				map.prepare(area);
 				let region = map.region(area, time);
 				for (pos, tile) in region {
 					total += 1;
@@ -394,5 +856,85 @@ mod tests {
 		eprintln!("total: {}, failed: {}", total, failed.len());
 		assert_eq!(failed, Vec::new());
 	}
-}
+	
+	#[test]
+	fn biome_cache_stays_bounded() {
+		let mut map = InfiniteMap::new(9876);
+		for x in 0..200 {
+			map.cache_biomes(Area::centered(Pos::new(x * 1000, 0), Pos::new(64, 64)));
+			assert!(map.biomes.len() <= MAX_CACHED_BIOMES);
+			assert!(map.rivers.len() <= MAX_CACHED_BIOMES);
+		}
+		// the most recently used cells are kept
+		assert!(map.biomes.contains_key(&map.base_bpos(Pos::new(198 * 1000, 0))));
+		let area = Area::centered(Pos::new(500, 500), Pos::new(64, 64));
+		map.prepare(area);
+		let region = map.region(area, Tickstamp(0));
+		assert_eq!(region, area.iter().map(|pos| (pos, map.cell(pos, Tickstamp(0)))).collect::<Vec<_>>());
+	}
+	
+	#[test]
+	fn climate_biomes_occur() {
+		let map = InfiniteMap::new(9876);
+		let biomes: Vec<Biome> = Area::centered(Pos::new(0, 0), Pos::new(40, 40)).iter()
+			.map(|bpos| map.biome_at(BPos(bpos)))
+			.collect();
+		for biome in [Biome::Desert, Biome::Highland, Biome::Snowfield, Biome::Forest, Biome::Lake] {
+			assert!(biomes.contains(&biome), "no {:?} biome found", biome);
+		}
+	}
+	
+	#[test]
+	fn rivers_flow_outside_lakes() {
+		let mut map = InfiniteMap::new(9876);
+		let area = Area::centered(Pos::new(0, 0), Pos::new(400, 400));
+		map.prepare(area);
+		let river_water = map.region(area, Tickstamp(0))
+			.into_iter()
+			.filter(|(pos, tile)| {
+				let biome = map.biome_at(map.biome_pos(*pos).0);
+				tile.ground == Ground::Water && biome != Biome::Lake && biome != Biome::Bog
+			})
+			.count();
+		assert!(river_water > 0);
+	}
+	
+	#[test]
+	fn points_of_interest_are_at_biome_cores() {
+		let mut map = InfiniteMap::new(9876);
+		let pois: Vec<(BPos, Poi)> = Area::centered(Pos::new(0, 0), Pos::new(20, 20)).iter()
+			.filter_map(|bpos| Some((BPos(bpos), map.poi_at(BPos(bpos))?)))
+			.collect();
+		for poi in [Poi::Ruin, Poi::Garden, Poi::Cache] {
+			assert!(pois.iter().any(|(_, p)| *p == poi), "no {:?} found", poi);
+		}
+		for (bpos, poi) in pois {
+			if poi == Poi::Cache {
+				assert_eq!(map.cell(map.biome_core(bpos), Tickstamp(0)).structure, Structure::Cache);
+			}
+		}
+	}
+	
+	#[test]
+	fn deserts_do_not_border_snowfields() {
+		let map = InfiniteMap::new(9876);
+		for bpos in Area::centered(Pos::new(0, 0), Pos::new(40, 40)).iter() {
+			if map.biome_at(BPos(bpos)) == Biome::Desert {
+				for d in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+					assert_ne!(map.biome_at(BPos(bpos + d)), Biome::Snowfield, "desert at {:?} borders snow", bpos);
+				}
+			}
+		}
+	}
 
+	#[test]
+	fn nearest_biomes_point_to_their_cores() {
+		let mut map = InfiniteMap::new(9876);
+		let pos = Pos::new(100, -60);
+		let nearest = map.nearest_biomes(pos);
+		assert!(nearest.contains(&(Biome::Start, map.biome_core(map.start_biome()))));
+		for (biome, core) in nearest {
+			assert_eq!(map.meta(core).map(|meta| meta.biome), Some(biome));
+		}
+	}
+}
@@ -0,0 +1,102 @@
+use crate::{
+	pos::{Pos, Area},
+	tickstamp::Tickstamp,
+	tile::{Tile, Ground, Structure},
+	random::{WhiteNoise, pick_weighted},
+	basemap::{BaseMap, InfiniteMap},
+	heightmap::{HeightMap, LazyHeightMap},
+	worldgen::WorldGenSettings,
+	util::parallel,
+};
+
+macro_rules! t {
+	($g:ident) => {Tile::ground(Ground::$g)};
+	($g:ident, $s:ident) => {Tile::structure(Ground::$g, Structure::$s)};
+}
+
+const CHAMBER_SIZE: i32 = 3;
+
+/// The underground layer below an InfiniteMap.
+/// Every cave entrance on the surface leads to a chamber with an exit at the same position.
+pub struct CaveMap {
+	seed: u32,
+	surface: InfiniteMap,
+	caves: LazyHeightMap,
+}
+
+impl CaveMap {
+	pub fn new(seed: u32, settings: WorldGenSettings) -> Self {
+		Self {
+			seed,
+			surface: InfiniteMap::with_settings(seed, settings),
+			caves: LazyHeightMap::new(seed + 6211, vec![(3, 0.15), (5, 0.25), (9, 0.3), (17, 0.3)]),
+		}
+	}
+
+	fn tile(&self, pos: Pos, caves: &impl HeightMap) -> Tile {
+		for entrance in self.surface.cave_entrances_near(pos, CHAMBER_SIZE * 2) {
+			let d = (pos - entrance).abs();
+			if d.is_zero() {
+				return t!(RockFloor, CaveExit);
+			} else if d.max() <= CHAMBER_SIZE {
+				return t!(RockFloor);
+			}
+		}
+		let rind = WhiteNoise::new(self.seed + 6212).gen(pos);
+		let height = caves.height(pos);
+		if height > 0.56 {
+			*pick_weighted(rind, &[
+				(t!(RockFloor), 50),
+				(t!(RockFloor, Gravel), 5),
+				(t!(RockFloor, Stone), 2),
+				(t!(RockFloor, Pebble), 1),
+			])
+		} else if height > 0.5 {
+			*pick_weighted(rind, &[
+				(t!(RockFloor, Rock), 12),
+				(t!(RockFloor, CopperOre), 1),
+			])
+		} else {
+			t!(RockFloor, RockMid)
+		}
+	}
+}
+
+impl BaseMap for CaveMap {
+	fn cell(&mut self, pos: Pos, _time: Tickstamp) -> Tile {
+		self.tile(pos, &self.caves)
+	}
+
+	fn prepare(&mut self, area: Area) {
+		self.surface.cache_biomes(area.grow(CHAMBER_SIZE * 2));
+	}
+
+	fn region(&self, area: Area, _time: Tickstamp) -> Vec<(Pos, Tile)> {
+		let caves = self.caves.cache(area);
+		parallel::in_bands(area, |band| band.iter().map(|pos| (pos, self.tile(pos, &caves))).collect())
+	}
+
+	fn player_spawn(&mut self) -> Pos {
+		self.surface.player_spawn()
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn cave_exit_is_below_entrance() {
+		let caves = CaveMap::new(9876, WorldGenSettings::default());
+		let entrance = (-20..20)
+			.flat_map(|x| (-20..20).map(move |y| Pos::new(x * 40, y * 40)))
+			.flat_map(|pos| caves.surface.cave_entrances_near(pos, 100))
+			.next()
+			.expect("no cave entrance found");
+		let mut surface = InfiniteMap::new(9876);
+		assert_eq!(surface.cell(entrance, Tickstamp(0)).structure, Structure::CaveEntrance);
+		assert_eq!(caves.tile(entrance, &caves.caves).structure, Structure::CaveExit);
+		assert_eq!(caves.tile(entrance + Pos::new(CHAMBER_SIZE, 0), &caves.caves), t!(RockFloor));
+	}
+}
@@ -0,0 +1,126 @@
+
+use crate::{
+	pos::{Pos, Area, Layer},
+	tile::Tile,
+	tickstamp::Tickstamp,
+	randomtick::CHUNK_SIZE,
+	persistence::LoaderError,
+	errors::AnyError,
+};
+
+pub type Change = (Tile, Tickstamp);
+pub type ChunkSave = Vec<(Pos, Change)>;
+
+/// Where maps keep the chunks that are not loaded
+pub trait ChunkStore: Send + Sync {
+	fn stored_chunks(&self, layer: Layer) -> Vec<Pos>;
+	fn load_chunk(&self, layer: Layer, chunk_pos: Pos) -> Result<ChunkSave, LoaderError>;
+	fn save_chunk(&self, layer: Layer, chunk_pos: Pos, save: ChunkSave) -> Result<(), AnyError>;
+}
+
+/// The changes to the base map in one square of CHUNK_SIZE by CHUNK_SIZE tiles
+#[derive(Debug, Clone)]
+pub struct Chunk {
+	changes: Vec<Option<Change>>,
+	len: usize,
+	/// The number of changes that are wild plants, which limits how far they spread
+	wild: usize,
+	pub dirty: bool,
+	pub last_active: Tickstamp,
+}
+
+impl Chunk {
+	pub fn new(time: Tickstamp) -> Self {
+		Self {
+			changes: vec![None; (CHUNK_SIZE * CHUNK_SIZE) as usize],
+			len: 0,
+			wild: 0,
+			dirty: false,
+			last_active: time,
+		}
+	}
+
+	pub fn area(chunk_pos: Pos) -> Area {
+		Area::new(chunk_pos * CHUNK_SIZE, Pos::new(CHUNK_SIZE, CHUNK_SIZE))
+	}
+
+	#[inline]
+	fn index(pos: Pos) -> usize {
+		(pos.x.rem_euclid(CHUNK_SIZE) + pos.y.rem_euclid(CHUNK_SIZE) * CHUNK_SIZE) as usize
+	}
+
+	#[inline]
+	pub fn get(&self, pos: Pos) -> Option<Change> {
+		self.changes[Self::index(pos)]
+	}
+
+	pub fn insert(&mut self, pos: Pos, change: Change) {
+		let slot = &mut self.changes[Self::index(pos)];
+		match slot {
+			None => self.len += 1,
+			Some((tile, _)) if tile.is_wild() => self.wild -= 1,
+			Some(_) => {}
+		}
+		if change.0.is_wild() {
+			self.wild += 1;
+		}
+		*slot = Some(change);
+		self.dirty = true;
+	}
+
+	pub fn remove(&mut self, pos: Pos) {
+		if let Some((tile, _)) = self.changes[Self::index(pos)].take() {
+			self.len -= 1;
+			if tile.is_wild() {
+				self.wild -= 1;
+			}
+			self.dirty = true;
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.len
+	}
+
+	pub fn wild(&self) -> usize {
+		self.wild
+	}
+
+	pub fn save(&self, chunk_pos: Pos) -> ChunkSave {
+		Self::area(chunk_pos).iter()
+			.filter_map(|pos| Some((pos, self.get(pos)?)))
+			.collect()
+	}
+
+	pub fn load(save: ChunkSave, time: Tickstamp) -> Self {
+		let mut chunk = Self::new(time);
+		for (pos, change) in save {
+			chunk.insert(pos, change);
+		}
+		chunk.dirty = false;
+		chunk
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tile::Ground;
+
+	#[test]
+	fn saved_chunk_loads_the_same() {
+		let chunk_pos = Pos::new(-2, 3);
+		let mut chunk = Chunk::new(Tickstamp(0));
+		let positions = [Pos::new(-128, 192), Pos::new(-65, 255), Pos::new(-100, 200)];
+		for (i, pos) in positions.iter().enumerate() {
+			chunk.insert(*pos, (Tile::ground(Ground::Dirt), Tickstamp(i as i64)));
+		}
+		chunk.remove(positions[2]);
+		assert_eq!(chunk.len(), 2);
+		let loaded = Chunk::load(chunk.save(chunk_pos), Tickstamp(5));
+		assert_eq!(loaded.save(chunk_pos), chunk.save(chunk_pos));
+		assert_eq!(loaded.get(positions[1]), Some((Tile::ground(Ground::Dirt), Tickstamp(1))));
+		assert!(!loaded.dirty);
+	}
+}
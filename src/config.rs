@@ -1,8 +1,10 @@
 
+use std::path::PathBuf;
 use clap::{Parser, Subcommand, Args};
 
 use crate::{
 	server::Address,
+	pos::Area,
 };
 
 #[derive(Debug, Parser)]
@@ -25,12 +27,65 @@ pub enum WorldAction {
 
 		#[arg(long)]
 		seed: u32,
+
+		/// A json5 file with a fixed map to use instead of the generated world
+		#[arg(long)]
+		map: Option<PathBuf>,
+
+		/// A json5 file with world generation settings
+		#[arg(long)]
+		worldgen: Option<PathBuf>,
+	},
+	/// Render a part of a world or a seed to a png or text file
+	Render{
+		/// The name of the world to render
+		#[arg(long, required_unless_present="seed", conflicts_with="seed")]
+		world: Option<String>,
+
+		/// Render a new world with this seed instead of a saved world
+		#[arg(long)]
+		seed: Option<u32>,
+
+		/// The area to render as "x,y,width,height"
+		#[arg(long)]
+		area: Area,
+
+		/// The number of tiles in each direction for one pixel or character
+		#[arg(long, default_value_t=1)]
+		scale: i32,
+
+		#[arg(long, default_value_t=0)]
+		layer: i32,
+
+		/// Mark claims and player positions
+		#[arg(long)]
+		overlay: bool,
+
+		/// Files ending in .png are rendered as image; anything else as text
+		output: PathBuf,
+	},
+	/// Print statistics about the land around the spawn of one or more seeds as json
+	Analyse{
+		#[arg(required=true)]
+		seeds: Vec<u32>,
+
+		/// How far from the spawn to look in each direction
+		#[arg(long, default_value_t=256)]
+		radius: i32,
+
+		/// A json5 file with world generation settings
+		#[arg(long)]
+		worldgen: Option<PathBuf>,
 	},
 	/// Benchmark world redraw
 	Bench{
 		/// the number of times to run the bench
 		#[arg(long, default_value_t=1000)]
 		iterations: usize,
+
+		/// the number of players that need a new view at the same time
+		#[arg(long, default_value_t=1)]
+		players: usize,
 	}
 
 }
@@ -5,7 +5,8 @@ use crate::{
 	PlayerId,
 	Direction,
 	Vec2,
-	timestamp::Timestamp
+	timestamp::Timestamp,
+	worldmessages::SectionEncoding,
 };
 
 
@@ -22,9 +23,10 @@ pub enum Control {
 
 #[derive(Debug, Clone)]
 pub enum Action {
-	Join(PlayerId),
+	Join(PlayerId, SectionEncoding),
 	Leave(PlayerId),
-	Input(PlayerId, Control, Timestamp)
+	Input(PlayerId, Control, Timestamp),
+	Redraw(PlayerId, Vec<String>),
 }
 
 
@@ -4,6 +4,7 @@ use serde::{Serialize, Deserialize};
 use crate::{
 	sprite::Sprite,
 	Pos,
+	pos::Layer,
 	PlayerId,
 	tickstamp::TickDuration,
 	util::HolderId,
@@ -21,6 +22,7 @@ pub enum Mind {
 pub struct Creature {
 	pub mind: Mind,
 	pub pos: Vec2,
+	pub layer: Layer,
 	pub shape: Rect,
 	pub cooldown: TickDuration,
 	pub walk_cooldown: TickDuration,
@@ -43,6 +45,7 @@ impl Creature {
 		Self {
 			mind: Mind::Player(playerid),
 			pos: saved.pos,
+			layer: saved.layer,
 			shape: Rect::new(Vec2::new(0.0, 0.0), Vec2::new(1.0, 1.0)),
 			cooldown: TickDuration(0),
 			walk_cooldown: TickDuration(0),
@@ -60,14 +63,17 @@ impl Creature {
 	pub fn save(&self) -> PlayerSave {
 		PlayerSave {
 			pos: self.pos,
+			layer: self.layer,
 			inventory: self.inventory.save()
 		}
 	}
 
-	pub fn view(&self) -> CreatureView {
+	pub fn view(&self, id: CreatureId) -> CreatureView {
 		CreatureView {
+			id,
 			pos: self.pos,
-			sprite: self.sprite
+			sprite: self.sprite,
+			name: self.player().map(|player| player.0)
 		}
 	}
 
@@ -76,7 +82,7 @@ impl Creature {
 	}
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
 pub struct CreatureId(pub usize);
 
 impl HolderId for CreatureId {
@@ -89,13 +95,16 @@ impl HolderId for CreatureId {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlayerSave {
 	pub inventory: InventorySave,
-	pub pos: Vec2
+	pub pos: Vec2,
+	#[serde(default)]
+	pub layer: Layer
 }
 
 impl PlayerSave {
 	pub fn new(pos: Vec2) -> Self {
 		Self {
 			pos,
+			layer: Layer::SURFACE,
 			inventory: Vec::new()
 		}
 	}
@@ -103,9 +112,13 @@ impl PlayerSave {
 
 #[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct CreatureView {
+	#[serde(rename = "i")]
+	pub id: CreatureId,
 	#[serde(rename = "s")]
 	pub sprite: Sprite,
 	#[serde(rename = "p")]
-	pub pos: Vec2
+	pub pos: Vec2,
+	#[serde(rename = "n", skip_serializing_if = "Option::is_none")]
+	pub name: Option<String>
 }
 
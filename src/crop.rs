@@ -149,6 +149,14 @@ impl Crop {
 		Self { typ: self.typ, flags: self.flags | WATERED }
 	}
 	
+	pub fn irrigated(&self) -> Option<Structure> {
+		if self.flags & WATERED == 0 {
+			Some(Structure::Crop(self.water()))
+		} else {
+			None
+		}
+	}
+	
 	fn fertilize(&self) -> Self {
 		Self { typ: self.typ, flags: self.flags | FERTILIZED }
 	}
@@ -0,0 +1,128 @@
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+use crate::{
+	pos::{Pos, Area},
+	tile::Tile,
+	tickstamp::Tickstamp,
+	basemap::BaseMap,
+	grid::Grid,
+	errors::Result,
+	aerr,
+};
+
+/// A handmade map as written in a map file.
+/// Each character in `map` is looked up in `legend`; everything outside the map is `outside`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FixedMapTemplate {
+	pub spawn: Pos,
+	#[serde(default)]
+	pub outside: Tile,
+	pub legend: HashMap<char, Tile>,
+	pub map: Vec<String>,
+}
+
+impl FixedMapTemplate {
+	pub fn load(path: &Path) -> Result<Self> {
+		let text = fs::read_to_string(path)?;
+		Self::parse(&text)
+	}
+
+	pub fn parse(text: &str) -> Result<Self> {
+		let template: Self = json5::from_str(text)?;
+		for (y, row) in template.map.iter().enumerate() {
+			if let Some(c) = row.chars().find(|c| !template.legend.contains_key(c)) {
+				return Err(aerr!("character {:?} on line {} of the map is not in the legend", c, y));
+			}
+		}
+		Ok(template)
+	}
+}
+
+
+pub struct FixedMap {
+	tiles: Grid<Tile>,
+	outside: Tile,
+	spawn: Pos,
+}
+
+impl FixedMap {
+	pub fn new(template: &FixedMapTemplate) -> Self {
+		let width = template.map.iter().map(|row| row.chars().count()).max().unwrap_or(0);
+		let size = Pos::new(width as i32, template.map.len() as i32);
+		let mut tiles = Grid::new(Area::new(Pos::zero(), size), template.outside);
+		for (y, row) in template.map.iter().enumerate() {
+			for (x, c) in row.chars().enumerate() {
+				if let Some(tile) = template.legend.get(&c) {
+					tiles.set(Pos::new(x as i32, y as i32), *tile);
+				}
+			}
+		}
+		Self {
+			tiles,
+			outside: template.outside,
+			spawn: template.spawn,
+		}
+	}
+
+	/// A map without any tiles of its own
+	pub fn filled(tile: Tile) -> Self {
+		Self {
+			tiles: Grid::new(Area::new(Pos::zero(), Pos::zero()), tile),
+			outside: tile,
+			spawn: Pos::zero(),
+		}
+	}
+}
+
+impl BaseMap for FixedMap {
+	fn cell(&mut self, pos: Pos, _time: Tickstamp) -> Tile {
+		self.tiles.get(pos).copied().unwrap_or(self.outside)
+	}
+
+	fn region(&self, area: Area, _time: Tickstamp) -> Vec<(Pos, Tile)> {
+		area.iter().map(|pos| (pos, self.tiles.get(pos).copied().unwrap_or(self.outside))).collect()
+	}
+
+	fn player_spawn(&mut self) -> Pos {
+		self.spawn
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tile::{Ground, Structure};
+
+	const TEMPLATE: &str = r#"{
+		spawn: [1, 1],
+		outside: ["Water", "Air"],
+		legend: {
+			"X": ["StoneFloor", "Wall"],
+			".": ["Dirt", "Air"],
+		},
+		map: [
+			"XXXX",
+			"X..X",
+			"XXXX",
+		],
+	}"#;
+
+	#[test]
+	fn fixed_map_follows_legend() {
+		let mut map = FixedMap::new(&FixedMapTemplate::parse(TEMPLATE).unwrap());
+		assert_eq!(map.player_spawn(), Pos::new(1, 1));
+		assert_eq!(map.cell(Pos::new(0, 0), Tickstamp(0)), Tile::structure(Ground::StoneFloor, Structure::Wall));
+		assert_eq!(map.cell(Pos::new(2, 1), Tickstamp(0)), Tile::ground(Ground::Dirt));
+		assert_eq!(map.cell(Pos::new(4, 1), Tickstamp(0)), Tile::ground(Ground::Water));
+		assert_eq!(map.cell(Pos::new(-1, -1), Tickstamp(0)), Tile::ground(Ground::Water));
+	}
+
+	#[test]
+	fn unknown_characters_are_rejected() {
+		assert!(FixedMapTemplate::parse(&TEMPLATE.replace("X..X", "X.?X")).is_err());
+	}
+}
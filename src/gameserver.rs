@@ -2,7 +2,7 @@
 
 use std::collections::HashMap;
 
-use serde_json::{Value, json};
+use serde_json::json;
 use serde::{Serialize, Deserialize};
 use unicode_categories::UnicodeCategories;
 use time::OffsetDateTime;
@@ -14,10 +14,12 @@ use crate::{
 		Server,
 		ServerEnum,
 		ConnectionId,
-		ServerError
+		ServerError,
+		Encoding,
 	},
 	PlayerId,
 	timestamp::Timestamp,
+	worldmessages::SectionEncoding,
 };
 
 
@@ -25,9 +27,23 @@ use crate::{
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all="lowercase")]
 enum Message {
-	Introduction(String),
+	Introduction(Introduction),
 	Chat(String),
-	Input(Control, u64)
+	Input(Control, u64),
+	/// The client is missing these chunks and needs its view area sent again
+	Redraw(Vec<String>),
+}
+
+/// Clients can introduce themselves with just a name, or with a name and the options they support
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+enum Introduction {
+	Name(String),
+	Options {
+		name: String,
+		#[serde(default)]
+		sections: SectionEncoding,
+	}
 }
 
 struct MessageError {
@@ -81,7 +97,7 @@ impl GameServer {
 		
 		let mut actions: Vec<Action> = Vec::new();
 		
-		let mut raw_messages: Vec<(ClientId, String)> = Vec::new();
+		let mut raw_messages: Vec<(ClientId, Vec<u8>, Encoding)> = Vec::new();
 		let mut to_remove: Vec<ClientId> = Vec::new();
 		
 		for (serverid, server) in self.servers.iter_mut() {
@@ -90,11 +106,11 @@ impl GameServer {
 				to_remove.push(ClientId(*serverid, connectionid));
 			}
 			for raw_message in message_updates.messages{
-				raw_messages.push((ClientId(*serverid, raw_message.connection), raw_message.content));
+				raw_messages.push((ClientId(*serverid, raw_message.connection), raw_message.content, raw_message.encoding));
 			}
 		}
-		for (clientid, content) in raw_messages {
-			match serde_json::from_str(&content) {
+		for (clientid, content, encoding) in raw_messages {
+			match encoding.decode(&content) {
 				Ok(msg) => {
 					match self.handle_message(clientid, msg){
 						Ok(Some(action)) => {actions.push(action);}
@@ -106,7 +122,7 @@ impl GameServer {
 					let _ = self.send_error(
 						clientid,
 						"invalidmessage",
-						&format!("Invalid message structure: {}", &content)
+						&format!("Invalid message structure: {}", String::from_utf8_lossy(&content))
 					);
 				}
 			}
@@ -122,47 +138,49 @@ impl GameServer {
 	}
 	
 	fn send_error(&mut self, clientid: ClientId, errname: &str, err_text: &str) -> Result<(), ServerError>{
-		self.servers.get_mut(&clientid.0)
-			.unwrap()
-			.send(clientid.1, json!(["error", errname, err_text]).to_string().as_str())
+		self.send_client(clientid, &json!(["error", errname, err_text]))
 	}
 	
-	pub fn broadcast_message(&mut self, text: &str){
-		println!("m {}      {}", text, OffsetDateTime::now_utc());
-		self.broadcast_json(json!(["message", text, ""]));
+	/// Encode the value in the format that was negotiated for the connection
+	fn send_client<T: Serialize + ?Sized>(&mut self, clientid: ClientId, value: &T) -> Result<(), ServerError> {
+		let ClientId(serverid, id) = clientid;
+		let server = self.servers.get_mut(&serverid).unwrap();
+		let encoding = server.encoding(id).ok_or(ServerError::InvalidIndex(id))?;
+		let data = encoding.encode(value).map_err(ServerError::Connection)?;
+		server.send(id, &data)
 	}
 	
-	pub fn broadcast_json(&mut self, value: Value){
-		self.broadcast(value.to_string().as_str());
+	pub fn broadcast_message(&mut self, text: &str){
+		println!("m {}      {}", text, OffsetDateTime::now_utc());
+		self.broadcast(&json!(["message", text, ""]));
 	}
 	
-	pub fn broadcast(&mut self, txt: &str){
-		for ClientId(serverid, id) in self.players.keys() {
-			let _ = self.servers.get_mut(serverid)
-				.unwrap()
-				.send(*id, txt);
+	pub fn broadcast<T: Serialize + ?Sized>(&mut self, value: &T){
+		let clients: Vec<ClientId> = self.players.keys().copied().collect();
+		for clientid in clients {
+			let _ = self.send_client(clientid, value);
 		}
 	}
 	
-	pub fn send(&mut self, player: &PlayerId, value: Value) -> Result<(), ServerError> {
+	pub fn send<T: Serialize + ?Sized>(&mut self, player: &PlayerId, value: &T) -> Result<(), ServerError> {
 		match self.connections.get(player) {
-			Some(ClientId(serverid, id)) => {
-				self.servers.get_mut(serverid)
-					.unwrap()
-					.send(*id, value.to_string().as_str())
-			}
+			Some(clientid) => self.send_client(*clientid, value),
 			None => Err(ServerError::Custom(format!("unknown player name {}", player)))
 		}
 	}
 	
 	pub fn send_player_error(&mut self, player: &PlayerId, errname: &str, err_text: &str) -> Result<(), ServerError> {
-		self.send(player, json!(["error", errname, err_text]))
+		self.send(player, &json!(["error", errname, err_text]))
 	}
 	
 	fn handle_message(&mut self, clientid: ClientId, msg: Message) -> Result<Option<Action>, MessageError> {
 		let id = clientid;
 		match msg {
-			Message::Introduction(name) => {
+			Message::Introduction(introduction) => {
+				let (name, sections) = match introduction {
+					Introduction::Name(name) => (name, SectionEncoding::default()),
+					Introduction::Options{name, sections} => (name, sections),
+				};
 				if name.len() > 60 {
 					return Err(merr!(name, "A name can not be longer than 60 bytes"));
 				}
@@ -185,10 +203,10 @@ impl GameServer {
 				self.players.insert(id, player.clone());
 				self.connections.insert(player.clone(), id);
 				let confirmation_message = json!(["connected", format!("successfully connected as {}", player)]);
-				if self.send(&player, confirmation_message).is_err() {
+				if self.send(&player, &confirmation_message).is_err() {
 					return Err(merr!("server", "unable to send connected message"))
 				}
-				Ok(Some(Action::Join(player)))
+				Ok(Some(Action::Join(player, sections)))
 			}
 			Message::Chat(text) => {
 				let player = self.players.get(&id).ok_or(merr!(action, "Set a valid name before you send any other messages"))?.clone();
@@ -199,9 +217,74 @@ impl GameServer {
 				let player = self.players.get(&id).ok_or(merr!(action, "Set a name before you send any other messages"))?;
 				Ok(Some(Action::Input(player.clone(), control, Timestamp::from_epoch_millis(millis))))
 			}
+			Message::Redraw(chunks) => {
+				let player = self.players.get(&id).ok_or(merr!(action, "Set a name before you send any other messages"))?;
+				Ok(Some(Action::Redraw(player.clone(), chunks)))
+			}
 		}
 	}
 }
 
 
 
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		Direction,
+		Vec2,
+		controls::Selector,
+	};
+
+	fn messages() -> Vec<Message> {
+		let controls = vec![
+			Control::Move(Direction::North),
+			Control::Movement(Vec2::new(0.5, -1.0)),
+			Control::Suicide,
+			Control::Interact(None),
+			Control::Interact(Some(Direction::West)),
+			Control::Select(Selector::Next),
+			Control::Select(Selector::Previous),
+			Control::Select(Selector::Idx(3)),
+			Control::MoveSelected(Selector::Idx(0)),
+		];
+		let mut messages = vec![
+			Message::Introduction(Introduction::Name("alice".to_string())),
+			Message::Introduction(Introduction::Options{name: "bob".to_string(), sections: SectionEncoding::Chunks}),
+			Message::Chat("hello world".to_string()),
+			Message::Redraw(vec!["32-447b9221a6c11202".to_string()]),
+		];
+		messages.extend(controls.into_iter().map(|control| Message::Input(control, 1_700_000_000_000)));
+		messages
+	}
+
+	#[test]
+	fn messages_round_trip() {
+		for encoding in [Encoding::Json, Encoding::MessagePack] {
+			for message in messages() {
+				let data = encoding.encode(&message).unwrap();
+				assert_eq!(Encoding::detect(&data), encoding);
+				let decoded: Message = encoding.decode(&data).unwrap();
+				assert_eq!(serde_json::to_value(&decoded).unwrap(), serde_json::to_value(&message).unwrap());
+			}
+		}
+	}
+
+	#[test]
+	fn client_messages_decode_from_both_encodings() {
+		let introduction = json!({"introduction": {"name": "carol", "sections": "chunks"}});
+		let input = json!({"input": [{"move": "south"}, 12]});
+		let redraw = json!({"redraw": ["32-447b9221a6c11202"]});
+		for encoding in [Encoding::Json, Encoding::MessagePack] {
+			for value in [&introduction, &input, &redraw] {
+				let data = encoding.encode(value).unwrap();
+				let message: Message = encoding.decode(&data).unwrap();
+				assert_eq!(&serde_json::to_value(&message).unwrap(), value);
+			}
+		}
+		let unknown = json!({"dance": 1});
+		assert!(Encoding::MessagePack.decode::<Message>(&Encoding::MessagePack.encode(&unknown).unwrap()).is_err());
+	}
+}
@@ -1,6 +1,7 @@
 
 use crate::{
-	pos::{Pos},
+	pos::{Pos, Area},
+	grid::Grid,
 	random::{WhiteNoise, randomize_u32}
 };
 
@@ -24,53 +25,40 @@ impl LazyHeightMap {
 		}
 	}
 
-	pub fn gen_f(&self, pos: Pos) -> f32 {
+	/// The noise, scale and weight of each octave
+	fn octaves(&self) -> impl Iterator<Item=(WhiteNoise, i32, f32)> + '_ {
 		let mut seed = self.seed;
-		let n = self.depth.iter()
-			.map(|(d, weight)| {
-				seed = randomize_u32(seed);
-				let c_n = self.gen_depth(pos, *d, seed);
+		self.depth.iter().map(move |(d, weight)| {
+			seed = randomize_u32(seed);
+			(WhiteNoise::new(seed), *d, *weight)
+		})
+	}
+
+	pub fn gen_f(&self, pos: Pos) -> f32 {
+		self.octaves()
+			.map(|(noise, d, weight)| {
+				let c_n = interpolate(pos, d, |p| noise.gen_f(p * d));
 				c_n * weight
 			})
-			.sum();
-		n
+			.sum()
 	}
 
-	fn gen_depth(&self, pos: Pos, factor: i32, seed: u32) -> f32 {
-		if factor == 1 {
-			return WhiteNoise::new(seed).gen_f(pos);
+	/// Precompute the lattice noise for all positions in area
+	pub fn cache(&self, area: Area) -> CachedHeightMap<'_> {
+		let octaves = self.octaves().map(|(noise, d, weight)| {
+			let lattice = Area::between(area.min() / d, (area.max() - Pos::new(1, 1)) / d + Pos::new(2, 2));
+			let mut grid = Grid::new(lattice, 0.0);
+			for p in lattice.iter() {
+				grid.set(p, noise.gen_f(p * d));
+			}
+			(d, weight, grid)
+		}).collect();
+		CachedHeightMap {
+			area,
+			lazy: self,
+			octaves
 		}
-		let pos_base = (pos / factor) * factor;
-		let diff = pos - pos_base;
-		let (u, v) = (diff.x as f32 / factor as f32, diff.y as f32 / factor as f32);
-		[
-				((0, 0), (1.0 - u) * (1.0 - v)),
-				((0, 1), (1.0 - u) * v),
-				((1, 0), u * (1.0 - v)),
-				((1, 1), u * v)
-		].into_iter().map(|((cx, cy), f)| {
-			let c = Pos::new(cx, cy) * factor;
-			WhiteNoise::new(seed).gen_f(pos_base + c) * f
-		}).sum::<f32>()
 	}
-
-	// pub fn cache(&self, request_area: Area) -> CachedHeightMap {
-	// 	let area = request_area.grow(2);
-	// 	let mut seed = self.seed;
-	// 	let grids = self.depth.iter().map(|(d, weight)| {
-	// 		seed = randomize_u32(seed);
-	// 		let noise = WhiteNoise::new(seed);
-	// 		let start = area.min() / *d;
-	// 		let end = area.max() / *d + Pos::new(1, 1);
-	// 		let da = Area::new(start, end - start).grow(1);
-	// 		let mut grid = Grid::new(da, 0.0);
-	// 		for dp in Area::new(start, end - start).iter() {
-	// 			grid.set(dp, noise.gen_f(dp * *d) * weight);
-	// 		}
-	// 		(*d, grid)
-	// 	}).collect();
-	// 	CachedHeightMap(grids)
-	// }
 }
 
 impl HeightMap for LazyHeightMap {
@@ -79,24 +67,58 @@ impl HeightMap for LazyHeightMap {
 	}
 }
 
-// pub struct CachedHeightMap(Vec<(i32, Grid<f32>)>);
-//
-// impl HeightMap for CachedHeightMap {
-// 	fn height(&self, pos: Pos) -> f32 {
-// 		self.0.iter().flat_map(|(d, g)| {
-// 			let pb = pos / *d;
-// 			let diff = pos - pb * *d;
-// 			let (u, v) = (diff.x as f32 / *d as f32, diff.y as f32 / *d as f32);
-// 			[
-// 					((0, 0), (1.0 - u) * (1.0 - v)),
-// 					((0, 1), (1.0 - u) * v),
-// 					((1, 0), u * (1.0 - v)),
-// 					((1, 1), u * v)
-// 			].into_iter().map(move |((cx, cy), f)| {
-// 				let c = Pos::new(cx, cy);
-// 				// println!("- {:?}, {:?}", pb + c, g.area);
-// 				g.get(pb + c).unwrap() * f
-// 			})
-// 		}).sum::<f32>()
-// 	}
-// }
+/// Bilinear interpolation between the lattice points around pos, given by their lattice coordinates
+#[inline]
+fn interpolate(pos: Pos, factor: i32, lattice: impl Fn(Pos) -> f32) -> f32 {
+	let pos_base = pos / factor;
+	if factor == 1 {
+		return lattice(pos_base);
+	}
+	let diff = pos - pos_base * factor;
+	let (u, v) = (diff.x as f32 / factor as f32, diff.y as f32 / factor as f32);
+	[
+			((0, 0), (1.0 - u) * (1.0 - v)),
+			((0, 1), (1.0 - u) * v),
+			((1, 0), u * (1.0 - v)),
+			((1, 1), u * v)
+	].into_iter().map(|((cx, cy), f)| {
+		lattice(pos_base + Pos::new(cx, cy)) * f
+	}).sum::<f32>()
+}
+
+/// A heightmap with the same heights as a LazyHeightMap, but faster within the area it was cached for
+pub struct CachedHeightMap<'a> {
+	area: Area,
+	lazy: &'a LazyHeightMap,
+	octaves: Vec<(i32, f32, Grid<f32>)>
+}
+
+impl HeightMap for CachedHeightMap<'_> {
+	fn height(&self, pos: Pos) -> f32 {
+		if !self.area.contains(pos) {
+			return self.lazy.height(pos);
+		}
+		self.octaves.iter()
+			.map(|(d, weight, grid)| {
+				let c_n = interpolate(pos, *d, |p| *grid.get(p).unwrap());
+				c_n * weight
+			})
+			.sum()
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn cached_heights_are_identical() {
+		let lazy = LazyHeightMap::new(9876, vec![(1, 0.1), (3, 0.2), (7, 0.3), (11, 0.4)]);
+		let area = Area::new(Pos::new(-37, -20), Pos::new(45, 31));
+		let cached = lazy.cache(area);
+		for pos in area.grow(3).iter() {
+			assert_eq!(cached.height(pos).to_bits(), lazy.height(pos).to_bits(), "different height at {:?}", pos);
+		}
+	}
+}
@@ -31,6 +31,7 @@ pub enum Item {
 	
 	#[assoc(name="reed")]
 	#[assoc(description="Some cut reeds")]
+	#[assoc(actions=vec![Action::Craft(CraftType::GardeningTable, Item::FishingRod, hashmap![Item::Stick => 1])])]
 	Reed,
 	
 	#[assoc(name="flower")]
@@ -44,7 +45,10 @@ pub enum Item {
 	
 	#[assoc(name="stone")]
 	#[assoc(description="A mid-size stone. Stones can be broken by smashing two together")]
-	#[assoc(actions=vec![Action::interact(Smash, 1, true)])]
+	#[assoc(actions=vec![
+		Action::Craft(CraftType::GardeningTable, Item::StoneHammer, hashmap![Item::Stick => 1, Item::Reed => 1]),
+		Action::interact(Smash, 1, true)
+	])]
 	Stone,
 	
 	#[assoc(name="sharp stone")]
@@ -63,8 +67,11 @@ pub enum Item {
 	FilledPitcher,
 	
 	#[assoc(name="hoe")]
-	#[assoc(description="A simple hoe that can be used to clear the ground of small vegetation")]
-	#[assoc(actions=vec![Action::Clear])]
+	#[assoc(description="A simple hoe that can be used to clear the ground of small vegetation and weeds, and to till dirt")]
+	#[assoc(actions=vec![
+		Action::interact(Weed, 1, false),
+		Action::Clear
+	])]
 	Hoe,
 	
 	#[assoc(name="green seed")]
@@ -91,8 +98,9 @@ pub enum Item {
 	Stick,
 	
 	#[assoc(name="discleaf")]
-	#[assoc(description="Disk leaf")]
+	#[assoc(description="Disk leaf. Can be steamed on a fire")]
 	#[assoc(actions=vec![
+		Action::Craft(CraftType::Cooking, Item::SteamedLeaf, HashMap::new()),
 		Action::interact(Fuel, 1, true)
 	])]
 	DiscLeaf,
@@ -107,6 +115,7 @@ pub enum Item {
 	#[assoc(name="hardwood stick")]
 	#[assoc(description="A strong stick")]
 	#[assoc(actions=vec![
+		Action::Craft(CraftType::GardeningTable, Item::Pickaxe, hashmap![Item::SharpStone => 2, Item::Reed => 1]),
 		Action::interact(Fuel, 2, true)
 	])]
 	HardwoodStick,
@@ -141,6 +150,62 @@ pub enum Item {
 	#[assoc(actions=vec![Action::interact(Fertilize, 1, true)])]
 	Ash,
 	
+	#[assoc(name="compost")]
+	#[assoc(description="Leaf mould from the forest floor. Can be used as fertilizer")]
+	#[assoc(actions=vec![Action::interact(Fertilize, 1, true)])]
+	Compost,
+	
+	#[assoc(name="stone hammer")]
+	#[assoc(description="A stone tied to a stick. It can break off pieces of the rock walls at the edge of a rock formation")]
+	#[assoc(actions=vec![
+		Action::interact(Mine, 2, false),
+		Action::interact(Smash, 2, false)
+	])]
+	StoneHammer,
+	
+	#[assoc(name="pickaxe")]
+	#[assoc(description="A hardwood pickaxe with sharp stone points. It can mine even the solid rock in the middle of a rock formation")]
+	#[assoc(actions=vec![
+		Action::interact(Mine, 3, false)
+	])]
+	Pickaxe,
+	
+	#[assoc(name="fishing rod")]
+	#[assoc(description="A stick with a line of twisted reed. Use it on open water to catch fish. Fish bite less often for a while after each attempt")]
+	#[assoc(actions=vec![Action::interact(Fish, 1, false)])]
+	FishingRod,
+	
+	#[assoc(name="perch")]
+	#[assoc(description="A raw perch, caught in a lake. Can be cooked on a fire")]
+	#[assoc(actions=vec![Action::Craft(CraftType::Cooking, Item::GrilledPerch, HashMap::new())])]
+	Perch,
+	
+	#[assoc(name="eel")]
+	#[assoc(description="A raw eel, caught in a bog. Can be cooked on a fire")]
+	#[assoc(actions=vec![Action::Craft(CraftType::Cooking, Item::SmokedEel, HashMap::new())])]
+	Eel,
+	
+	#[assoc(name="cactus fruit")]
+	#[assoc(description="The fruit of a desert cactus. Can be roasted on a fire")]
+	#[assoc(actions=vec![Action::Craft(CraftType::Cooking, Item::RoastedCactusFruit, HashMap::new())])]
+	CactusFruit,
+	
+	#[assoc(name="roasted cactus fruit")]
+	#[assoc(description="A cactus fruit roasted over a fire. Sweet and juicy")]
+	RoastedCactusFruit,
+	
+	#[assoc(name="grilled perch")]
+	#[assoc(description="A perch grilled over a fire")]
+	GrilledPerch,
+	
+	#[assoc(name="smoked eel")]
+	#[assoc(description="An eel smoked over a fire")]
+	SmokedEel,
+	
+	#[assoc(name="steamed leaf")]
+	#[assoc(description="A disc leaf steamed over a fire. Soft and filling")]
+	SteamedLeaf,
+	
 	#[assoc(name="axe")]
 	#[assoc(description="A wooden axe")]
 	#[assoc(actions=vec![
@@ -148,6 +213,15 @@ pub enum Item {
 	])]
 	Axe,
 	
+	#[assoc(name="copper ore")]
+	#[assoc(description="A lump of copper ore from deep underground")]
+	CopperOre,
+	
+	#[assoc(name="sapling")]
+	#[assoc(description="A young tree. Can be planted in dirt where it will grow into a tree")]
+	#[assoc(actions=vec![Action::Plant(Structure::Sapling)])]
+	Sapling,
+	
 	#[assoc(name="log")]
 	#[assoc(description="Wooden log")]
 	#[assoc(actions=vec![
@@ -167,10 +241,16 @@ pub enum Item {
 	#[assoc(name="plank")]
 	#[assoc(description="Wooden plank")]
 	#[assoc(actions=vec![
+		Action::Craft(CraftType::GardeningTable, Item::Shovel, hashmap![Item::Stick => 1]),
 		Action::Build(Structure::PlankWall, HashMap::new()),
 		Action::interact(Fuel, 2, true)
 	])]
 	Plank,
+	
+	#[assoc(name="shovel")]
+	#[assoc(description="A wooden shovel. Can dig channels in dirt to lead water from a lake to crops, or fill them up again")]
+	#[assoc(actions=vec![Action::Dig])]
+	Shovel,
 }
 
 
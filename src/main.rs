@@ -5,23 +5,30 @@ use clap::Parser;
 use time::OffsetDateTime;
 
 mod action;
+mod analysis;
 mod basemap;
+mod cavemap;
+mod chunk;
 mod config;
 mod controls;
 mod creature;
 mod crop;
 mod errors;
+mod fixedmap;
 mod heightmap;
 mod gameserver;
 mod grid;
 mod inventory;
 mod item;
+mod legacymap;
 mod map;
 mod persistence;
+mod poi;
 mod player;
 mod pos;
 mod random;
 mod randomtick;
+mod render;
 mod server;
 mod sprite;
 mod tickstamp;
@@ -30,10 +37,11 @@ mod timestamp;
 mod util;
 mod vec2;
 mod world;
+mod worldgen;
 mod worldmessages;
 
 use self::{
-	pos::{Pos, Direction},
+	pos::{Pos, Area, Layer, Direction},
 	vec2::Vec2,
 	player::PlayerId,
 	errors::{Result},
@@ -46,6 +54,11 @@ use self::{
 	worldmessages::MessageCache,
 	persistence::{PersistentStorage, FileStorage, LoaderError},
 	config::{Config, WorldAction, WorldConfig},
+	basemap::WorldType,
+	fixedmap::FixedMapTemplate,
+	render::{MapImage, Marker},
+	analysis::SeedAnalysis,
+	worldgen::WorldGenSettings,
 };
 
 
@@ -55,10 +68,15 @@ fn main(){
 	let config = Config::parse();
 	
 	match config.world_action {
-		WorldAction::New{conf, seed} => {
+		WorldAction::New{conf, seed, map, worldgen} => {
+			let world_type = match map {
+				Some(path) => WorldType::Fixed(FixedMapTemplate::load(&path).expect("Can't load map file")),
+				None => WorldType::Infinite
+			};
+			let worldgen = load_worldgen(worldgen);
 			let persistence = FileStorage::initialize(&conf.name).unwrap();
 			if let Err(LoaderError::MissingResource(_)) = persistence.load_world() {
-				start_world(World::new(conf.name.clone(), seed), persistence, conf);
+				start_world(World::new(conf.name.clone(), seed, world_type, worldgen), persistence, conf);
 			} else {
 				panic!("World '{}' already exists", &conf.name);
 			}
@@ -67,14 +85,33 @@ fn main(){
 			let persistence = FileStorage::initialize(&conf.name).unwrap();
 			start_world(World::load(persistence.load_world().expect("Can't load world")), persistence, conf);
 		}
-		WorldAction::Bench{iterations} => {
-			bench_view(iterations);
+		WorldAction::Render{world, seed, area, scale, layer, overlay, output} => {
+			if let Err(err) = render(world, seed, area, scale, Layer(layer), overlay, &output) {
+				eprintln!("Can't render world. {}", err);
+				std::process::exit(1);
+			}
+		}
+		WorldAction::Analyse{seeds, radius, worldgen} => {
+			let worldgen = load_worldgen(worldgen);
+			let analyses: Vec<SeedAnalysis> = seeds.into_iter()
+				.map(|seed| SeedAnalysis::new(seed, radius, &worldgen))
+				.collect();
+			println!("{}", serde_json::to_string_pretty(&analyses).unwrap());
+		}
+		WorldAction::Bench{iterations, players} => {
+			bench_view(iterations, players);
 		}
 	}
 }
 
+fn load_worldgen(path: Option<std::path::PathBuf>) -> WorldGenSettings {
+	path.map(|path| WorldGenSettings::load(&path).expect("Can't load world generation settings"))
+		.unwrap_or_default()
+}
+
 fn start_world(mut world: World, persistence: FileStorage, config: WorldConfig) {
 	
+	world.set_chunk_store(Arc::new(persistence.clone()));
 
 	eprintln!("Server admin(s): {}", config.admins);
 
@@ -123,7 +160,8 @@ fn start_world(mut world: World, persistence: FileStorage, config: WorldConfig)
 						eprintln!("error controlling player {:?}: {:?}", player, err);
 					}
 				}
-				Action::Join(player) => {
+				Action::Join(player, sections) => {
+					message_cache.set_encoding(&player, sections);
 					let playersave = match persistence.load_player(&player) {
 						Ok(save) => save,
 						Err(LoaderError::MissingResource(_)) => world.default_player(),
@@ -142,6 +180,12 @@ fn start_world(mut world: World, persistence: FileStorage, config: WorldConfig)
 						}
 					}
 				}
+				Action::Redraw(player, chunks) => {
+					message_cache.forget_chunks(&player, &chunks);
+					if let Err(err) = world.redraw_player(&player) {
+						eprintln!("error redrawing player {:?}: {:?}", player, err);
+					}
+				}
 				Action::Leave(player) => {
 					if world.has_player(&player) {
 						persistence.save_player(&player, world.save_player(&player).unwrap()).unwrap();
@@ -166,14 +210,14 @@ fn start_world(mut world: World, persistence: FileStorage, config: WorldConfig)
 			if message.is_empty(){
 				continue;
 			}
-// 			eprintln!("m {}", message.to_json());
-			if let Err(err) = gameserver.send(&player, message.to_json()) {
+// 			eprintln!("m {}", serde_json::to_string(&message).unwrap());
+			if let Err(err) = gameserver.send(&player, &message) {
 				eprintln!("Error: failed to send to {:?}: {:?}", player, err);
 			}
 		}
 		let send_done = Instant::now();
 		if world.time.0 % 100 == 1 {
-			save(&world, &persistence);
+			save(&mut world, &persistence);
 		}
 		let save_done = Instant::now();
 		let elapsed_time = update_start.elapsed();
@@ -190,11 +234,14 @@ fn start_world(mut world: World, persistence: FileStorage, config: WorldConfig)
 		}
 		thread::sleep(Duration::from_millis(config.step_duration).saturating_sub(elapsed_time));
 	}
-	save(&world, &persistence);
+	save(&mut world, &persistence);
 	eprintln!("shutting down on {}", OffsetDateTime::now_utc());
 }
 
-fn save(world: &World, persistence: &impl PersistentStorage) {
+fn save(world: &mut World, persistence: &impl PersistentStorage) {
+	if let Err(err) = world.save_chunks() {
+		eprintln!("Error: can not save all chunks, they will be saved again later: {:?}", err);
+	}
 	persistence.save_world(world.save()).unwrap();
 	for player in world.list_players() {
 		persistence.save_player(&player, world.save_player(&player).unwrap()).unwrap();
@@ -202,18 +249,60 @@ fn save(world: &World, persistence: &impl PersistentStorage) {
 	eprintln!("saved world {} on step {}", world.name, world.time.0);
 }
 
+fn render(world_name: Option<String>, seed: Option<u32>, area: Area, scale: i32, layer: Layer, overlay: bool, output: &std::path::Path) -> Result<()> {
+	let (mut world, persistence) = if let Some(name) = world_name {
+		let persistence = FileStorage::initialize(&name)
+			.map_err(|e| err!("can't open world {}: {:?}", name, e))?;
+		let save = persistence.load_world()
+			.map_err(|e| err!("can't load world {}: {:?}", name, e))?;
+		let mut world = World::load(save);
+		world.set_chunk_store(Arc::new(persistence.clone()));
+		(world, Some(persistence))
+	} else {
+		// clap makes sure that there is a seed when there is no world
+		let seed = seed.ok_or_else(|| err!("either a world or a seed is required"))?;
+		(World::new("render".to_string(), seed, WorldType::Infinite, WorldGenSettings::default()), None)
+	};
+	let mut image = MapImage::new(world.map(layer), area, scale.max(1));
+	if overlay {
+		if layer == Layer::SURFACE {
+			for claim in world.claims() {
+				image.mark(claim, Marker::Claim);
+			}
+		}
+		if let Some(persistence) = persistence {
+			for player in persistence.list_players() {
+				let save = persistence.load_player(&player)
+					.map_err(|e| err!("can't load player {}: {:?}", player, e))?;
+				if save.layer == layer {
+					image.mark(save.pos.round(), Marker::Player);
+				}
+			}
+		}
+	}
+	if output.extension().is_some_and(|ext| ext == "png") {
+		image.to_png(output)
+	} else {
+		std::fs::write(output, image.to_ascii())?;
+		Ok(())
+	}
+}
 
-fn bench_view(iterations: usize) {
-	let mut world = World::new("bench".to_string(), 9876);
+fn bench_view(iterations: usize, players: usize) {
+	let mut world = World::new("bench".to_string(), 9876, WorldType::Infinite, WorldGenSettings::default());
 	let mut player_save = world.default_player();
-	let player_id = PlayerId("Player".to_string());
+	let player_ids: Vec<PlayerId> = (0..players).map(|n| PlayerId(format!("Player{}", n))).collect();
 	let now = Instant::now();
 	for i in 0..iterations {
-		player_save.pos = Vec2::new(i as f32 * 121. - 22., i as f32 * 8. - 63.);
-		world.add_player(&player_id, player_save.clone()).unwrap();
+		for (n, player_id) in player_ids.iter().enumerate() {
+			player_save.pos = Vec2::new(i as f32 * 121. - 22., i as f32 * 8. - 63. + n as f32 * 997.);
+			world.add_player(player_id, player_save.clone()).unwrap();
+		}
 		world.update();
 		world.view();
-		world.remove_player(&player_id).unwrap();
+		for player_id in player_ids.iter() {
+			world.remove_player(player_id).unwrap();
+		}
 		world.update();
 	}
 	eprintln!("millis: {}", now.elapsed().as_millis());
@@ -1,74 +1,312 @@
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque, BinaryHeap};
+use std::cmp::{Reverse, Ordering};
+use serde::{Serialize, Deserialize};
+use std::sync::Arc;
 use crate::{
-	pos::{Pos, Area, Direction},
-	tile::{Tile, Structure, Ground},
-	basemap::{BaseMap, InfiniteMap},
+	pos::{Pos, Area, Direction, Layer},
+	tile::{Tile, Structure, Ground, Habitat},
+	basemap::{BaseMap, BaseMapEnum, Biome, TileMeta},
 	tickstamp::{Tickstamp, TickDuration},
-	randomtick
+	random,
+	randomtick::{self, CHUNK_SIZE},
+	chunk::{Chunk, Change, ChunkStore},
+	errors::AnyError,
+	persistence::LoaderError,
+	aerr,
 };
 
+const WEED_RANGE: i32 = 24;
+const SPREAD_ODDS: f32 = 0.25;
+const MAX_SPREAD_CHANGES: usize = 256;
+const MAX_CHANNEL_FLOW: i32 = 32;
+/// Chunks outside all loaded areas are unloaded after this many ticks
+const UNLOAD_DELAY: i64 = 600;
+
 pub struct Map {
-	basemap: InfiniteMap,
-	changes: HashMap<Pos, (Tile, Tickstamp)>,
+	basemap: BaseMapEnum,
+	chunks: HashMap<Pos, Chunk>,
+	store: Option<(Layer, Arc<dyn ChunkStore>)>,
+	/// Chunks that are in the store but not loaded
+	stored: HashSet<Pos>,
+	/// Chunks that failed to load. They are never changed or saved, so the stored chunk is kept as it is
+	broken: HashSet<Pos>,
+	claims: Vec<Pos>,
+	timers: BinaryHeap<Reverse<Timer>>,
 	time: Tickstamp,
 	modifications: HashSet<Pos>
 }
 
+/// The structure at pos changes at time, unless the tile has been changed since it was set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timer {
+	time: Tickstamp,
+	pos: Pos,
+	set: Tickstamp,
+	/// The structure that was set. Timers saved without it only check the time
+	#[serde(default)]
+	structure: Option<Structure>,
+}
+
+impl Ord for Timer {
+	fn cmp(&self, other: &Self) -> Ordering {
+		(self.time, self.set, self.pos.x, self.pos.y).cmp(&(other.time, other.set, other.pos.x, other.pos.y))
+	}
+}
+
+impl PartialOrd for Timer {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
 impl Map {
 	
+	#[cfg(test)]
 	pub fn new(seed: u32, time: Tickstamp) -> Self {
+		Self::with_basemap(crate::basemap::InfiniteMap::new(seed).into(), time)
+	}
+	
+	pub fn with_basemap(basemap: BaseMapEnum, time: Tickstamp) -> Self {
 		Self {
-			basemap: InfiniteMap::new(seed),
-			changes: HashMap::new(),
+			basemap,
+			chunks: HashMap::new(),
+			store: None,
+			stored: HashSet::new(),
+			broken: HashSet::new(),
+			claims: Vec::new(),
+			timers: BinaryHeap::new(),
 			time,
 			modifications: HashSet::new()
 		}
 	}
 	
+	/// Keep chunks in the store instead of in memory when they are not in use.
+	/// All chunks that are loaded now will be saved to the store
+	pub fn set_store(&mut self, layer: Layer, store: Arc<dyn ChunkStore>) {
+		self.stored = store.stored_chunks(layer).into_iter()
+			.filter(|chunk_pos| !self.chunks.contains_key(chunk_pos))
+			.collect();
+		for chunk in self.chunks.values_mut() {
+			chunk.dirty = true;
+		}
+		self.store = Some((layer, store));
+	}
+	
+	/// Loading is only tried once; when it fails the chunk is marked as broken and every later call fails too
+	fn chunk(&mut self, chunk_pos: Pos) -> Result<Option<&mut Chunk>, LoaderError> {
+		if !self.broken.is_empty() && self.broken.contains(&chunk_pos) {
+			return Err(LoaderError::InvalidResource(aerr!("chunk {:?} failed to load before", chunk_pos)));
+		}
+		if !self.stored.is_empty() && self.stored.remove(&chunk_pos) {
+			let (layer, store) = self.store.as_ref().unwrap();
+			match store.load_chunk(*layer, chunk_pos) {
+				Ok(save) => {
+					self.chunks.insert(chunk_pos, Chunk::load(save, self.time));
+				}
+				Err(err) => {
+					eprintln!("Can't load chunk {:?}, using the base map instead and leaving it unchanged: {:?}", chunk_pos, err);
+					self.broken.insert(chunk_pos);
+					return Err(err);
+				}
+			}
+		}
+		Ok(self.chunks.get_mut(&chunk_pos))
+	}
+	
+	/// Broken chunks read as having no changes
+	fn loaded_chunk(&mut self, chunk_pos: Pos) -> Option<&mut Chunk> {
+		self.chunk(chunk_pos).ok().flatten()
+	}
+	
+	/// Whether the tile at pos can be changed. This fails when its chunk could not be loaded
+	pub fn changeable(&mut self, pos: Pos) -> Result<(), LoaderError> {
+		self.chunk(pos / CHUNK_SIZE).map(|_| ())
+	}
+	
+	fn change(&mut self, pos: Pos) -> Option<Change> {
+		self.loaded_chunk(pos / CHUNK_SIZE)?.get(pos)
+	}
+	
+	/// Changes to broken chunks are dropped
+	fn insert_change(&mut self, pos: Pos, tile: Tile, time: Tickstamp) {
+		let chunk_pos = pos / CHUNK_SIZE;
+		match self.chunk(chunk_pos) {
+			Ok(Some(_)) => {}
+			Ok(None) => {
+				self.chunks.insert(chunk_pos, Chunk::new(self.time));
+			}
+			Err(_) => return,
+		}
+		self.chunks.get_mut(&chunk_pos).unwrap().insert(pos, (tile, time));
+	}
+	
+	fn remove_change(&mut self, pos: Pos) {
+		if let Some(chunk) = self.loaded_chunk(pos / CHUNK_SIZE) {
+			chunk.remove(pos);
+		}
+	}
+	
 	fn base_cell(&mut self, pos: Pos) -> Tile {
 		self.basemap.cell(pos, self.time)
 	}
 	
 	pub fn cell(&mut self, pos: Pos) -> Tile {
-		self.changes.get(&pos).map(|change| change.0).unwrap_or_else(|| self.base_cell(pos))
+		self.change(pos).map(|change| change.0).unwrap_or_else(|| self.base_cell(pos))
 	}
 
 	pub fn load_area(&mut self, area: Area) -> impl Iterator<Item = (Pos, Tile)> + '_ {
-		// let base_grid = self.basemap.region(area, self.time);
-		self.basemap.region(area, self.time).into_iter().map(|(pos, base_cell)| {
-			// let base_cell = self.base_cell(pos);
+		self.prepare(area);
+		let region = self.base_region(area);
+		self.apply_changes(region)
+	}
+	
+	/// Fill the base map caches for area, so that base_region is fast there
+	pub fn prepare(&mut self, area: Area) {
+		self.basemap.prepare(area);
+	}
+	
+	/// The generated tiles of area without the changes, so it can run for several areas at once
+	pub fn base_region(&self, area: Area) -> Vec<(Pos, Tile)> {
+		self.basemap.region(area, self.time)
+	}
+	
+	/// Catch up on the ticks of a base region and put the changes over it
+	pub fn apply_changes(&mut self, region: Vec<(Pos, Tile)>) -> impl Iterator<Item = (Pos, Tile)> + '_ {
+		region.into_iter().map(|(pos, base_cell)| {
 			self.tick_one(pos, base_cell);
-			let cell = self.changes.get(&pos).map(|change| change.0).unwrap_or(base_cell);
+			let cell = self.change(pos).map(|change| change.0).unwrap_or(base_cell);
 			(pos, cell)
 		})
 	}
 
+	/// Tiles in broken chunks are left as they are, see changeable
 	pub fn set(&mut self, pos: Pos, tile: Tile) {
+		if self.changeable(pos).is_err() {
+			return;
+		}
 		if tile == self.base_cell(pos) {
-			self.changes.remove(&pos);
+			self.remove_change(pos);
 		} else {
-			self.changes.insert(pos, (tile, self.time));
+			self.insert_change(pos, tile, self.time);
+		}
+		if let Some((duration, _)) = tile.structure.timer() {
+			self.timers.push(Reverse(Timer {time: self.time + TickDuration(duration), pos, set: self.time, structure: Some(tile.structure)}));
 		}
 		self.modifications.insert(pos);
 	}
 	
+	fn run_timers(&mut self) {
+		while let Some(Reverse(timer)) = self.timers.peek().copied() {
+			if timer.time > self.time {
+				break;
+			}
+			self.timers.pop();
+			let Some((tile, set)) = self.change(timer.pos) else {
+				continue
+			};
+			if set != timer.set || timer.structure.is_some_and(|structure| structure != tile.structure) {
+				continue;
+			}
+			if let Some((_, next)) = tile.structure.timer() {
+				self.set(timer.pos, Tile::structure(tile.ground, next));
+			}
+		}
+	}
+	
+	pub fn timers(&self) -> Vec<Timer> {
+		self.timers.iter().map(|timer| timer.0).collect()
+	}
+	
+	pub fn load_timers(&mut self, timers: Vec<Timer>) {
+		self.timers.extend(timers.into_iter().map(Reverse));
+	}
+	
 	pub fn set_structure(&mut self, pos: Pos, structure: Structure) {
 		let new_tile = Tile::structure(self.cell(pos).ground, structure) ;
 		self.set(pos, new_tile )
 	}
 	
 	pub fn set_ground(&mut self, pos: Pos, ground: Ground) {
+		let old_ground = self.cell(pos).ground;
 		let new_tile = Tile::structure(ground, self.cell(pos).structure);
-		self.set(pos, new_tile )
+		self.set(pos, new_tile );
+		if old_ground.is_channel() || old_ground.water_source() || ground.is_channel() || ground.water_source() {
+			self.update_channels(pos);
+		}
+	}
+	
+	/// Water flows at most MAX_CHANNEL_FLOW tiles from its source.
+	/// Only channels that close to pos can be affected, and their sources are at most twice as far away.
+	fn update_channels(&mut self, pos: Pos) {
+		let mut region: HashMap<Pos, i32> = HashMap::from([(pos, 0)]);
+		let mut queue = VecDeque::from([pos]);
+		while let Some(p) = queue.pop_front() {
+			let distance = region[&p];
+			if distance >= 2 * MAX_CHANNEL_FLOW {
+				continue;
+			}
+			for d in Direction::DIRECTIONS {
+				let npos = p + d;
+				if !region.contains_key(&npos) && self.cell(npos).ground.is_channel() {
+					region.insert(npos, distance + 1);
+					queue.push_back(npos);
+				}
+			}
+		}
+		let mut flow: HashMap<Pos, i32> = HashMap::new();
+		for &p in region.keys() {
+			if self.cell(p).ground.is_channel()
+					&& Direction::DIRECTIONS.iter().any(|d| self.cell(p + *d).ground.water_source()) {
+				flow.insert(p, 1);
+				queue.push_back(p);
+			}
+		}
+		while let Some(p) = queue.pop_front() {
+			let distance = flow[&p];
+			if distance >= MAX_CHANNEL_FLOW {
+				continue;
+			}
+			for d in Direction::DIRECTIONS {
+				let npos = p + d;
+				if region.contains_key(&npos) && !flow.contains_key(&npos) && self.cell(npos).ground.is_channel() {
+					flow.insert(npos, distance + 1);
+					queue.push_back(npos);
+				}
+			}
+		}
+		for (p, distance) in region {
+			let tile = self.cell(p);
+			if distance > MAX_CHANNEL_FLOW || !tile.ground.is_channel() {
+				continue;
+			}
+			let ground = if flow.contains_key(&p) { Ground::WetChannel } else { Ground::Channel };
+			if ground != tile.ground {
+				self.set(p, Tile::structure(ground, tile.structure));
+			}
+		}
+	}
+	
+	fn irrigated(&mut self, pos: Pos) -> bool {
+		Direction::DIRECTIONS.iter().any(|d| self.cell(pos + *d).ground == Ground::WetChannel)
 	}
 	
 	pub fn player_spawn(&mut self) -> Pos {
 		self.basemap.player_spawn()
 	}
 	
-	pub fn tick(&mut self, time: Tickstamp, areas: Vec<Area>) {
+	pub fn meta(&mut self, pos: Pos) -> Option<TileMeta> {
+		self.basemap.meta(pos)
+	}
+	
+	pub fn nearest_biomes(&mut self, pos: Pos) -> Vec<(Biome, Pos)> {
+		self.basemap.nearest_biomes(pos)
+	}
+	
+	pub fn tick(&mut self, time: Tickstamp, areas: Vec<Area>, claims: Vec<Pos>) {
 		self.time = time;
+		self.claims = claims;
+		self.run_timers();
 		let chunk_size = randomtick::CHUNK_SIZE;
 		let tick_pos = randomtick::tick_position(time);
 		let tick_positions = areas.iter()
@@ -84,30 +322,71 @@ impl Map {
 		for pos in tick_positions {
 			let base_cell = self.basemap.cell(pos, self.time);
 			self.tick_one(pos, base_cell);
+			self.spread(pos);
+		}
+		if let Err(err) = self.unload_inactive(&areas) {
+			eprintln!("Can't save unloaded chunks, keeping them loaded: {:?}", err);
+		}
+	}
+	
+	fn spread(&mut self, pos: Pos) {
+		let seed = self.time.random_seed() ^ random::randomize_pos(pos);
+		if random::random_float(seed) >= SPREAD_ODDS {
+			return;
+		}
+		let npos = pos + *random::pick(random::randomize_u32(seed), &Direction::DIRECTIONS);
+		let wild = self.loaded_chunk(npos / CHUNK_SIZE).map_or(0, |chunk| chunk.wild());
+		if wild >= MAX_SPREAD_CHANGES {
+			return;
+		}
+		let Some(product) = self.cell(pos).spread_onto(self.cell(npos)) else {
+			return;
+		};
+		let suitable = match product.structure.habitat() {
+			Some(Habitat::Shore) => Direction::DIRECTIONS.iter().any(|d| self.cell(npos + *d).ground.water_source()),
+			Some(Habitat::Claims) => self.near_claim(npos),
+			None => true,
+		};
+		if suitable {
+			self.insert_change(npos, product, self.time);
+			self.modifications.insert(npos);
 		}
 	}
 	
+	fn near_claim(&self, pos: Pos) -> bool {
+		self.claims.iter().any(|claim| claim.distance_to(pos) <= WEED_RANGE)
+	}
+	
 	fn tick_one(&mut self, pos: Pos, base_cell: Tile) {
 		self.modifications.insert(pos);
 		let tick_interval = randomtick::CHUNK_AREA as i64;
-		if let Some((mut built, mut built_time)) = self.changes.get(&pos) {
-			while let Some((nticks, stage, surround)) = built.grow() {
-				let update_time = built_time + TickDuration(nticks * tick_interval);
+		if let Some((mut built, mut built_time)) = self.change(pos) {
+			loop {
+				if let Some(watered) = built.structure.irrigated() {
+					if self.irrigated(pos) {
+						built.structure = watered;
+						self.insert_change(pos, built, built_time);
+					}
+				}
+				let Some((nticks, stage, surround)) = built.grow() else {
+					break
+				};
+				let update_time = built_time + TickDuration(nticks * tick_interval * built.ground.growth_percent() / 100);
 				if update_time <= self.time {
 					built.structure = stage;
 					built_time = update_time;
-					self.changes.insert(pos, (built, built_time));
+					self.insert_change(pos, built, built_time);
 					if let Some(shoot) = surround {
 						for d in Direction::DIRECTIONS {
 							let npos = pos + d;
 							let mut ntile = self.cell(npos);
 							if let Some(product) = shoot.joined(ntile.structure) {
 								ntile.structure = product;
-								self.changes.insert(npos, (ntile, built_time));
+								self.insert_change(npos, ntile, built_time);
 								self.modifications.insert(npos);
 							} else if ntile.structure.is_open() {
 								ntile.structure = shoot;
-								self.changes.insert(npos, (ntile, built_time));
+								self.insert_change(npos, ntile, built_time);
 								self.modifications.insert(npos);
 							}
 						}
@@ -119,7 +398,13 @@ impl Map {
 			if built.structure.is_open()
 					&& (built.ground.restoring() || built.ground == base_cell.ground)
 					&& base_cell.structure.is_open() {
-				self.changes.remove(&pos);
+				if built.ground == Ground::Dirt && self.near_claim(pos) {
+					if built_time + TickDuration(tick_interval) <= self.time {
+						self.insert_change(pos, Tile::structure(Ground::Dirt, Structure::Weeds), self.time);
+					}
+				} else {
+					self.remove_change(pos);
+				}
 			}
 		}
 	}
@@ -132,19 +417,379 @@ impl Map {
 		self.modifications.clone().into_iter().map(|pos| (pos, self.cell(pos))).collect()
 	}
 	
+	/// The changes that are not kept in a chunk store
 	pub fn save(&self) -> MapSave {
-		self.changes.clone().into_iter().collect()
+		if self.store.is_some() {
+			return Vec::new();
+		}
+		self.chunks.iter()
+			.flat_map(|(chunk_pos, chunk)| chunk.save(*chunk_pos))
+			.collect()
 	}
 	
-	pub fn load(changes: MapSave, time: Tickstamp, seed: u32) -> Self {
-		Self {
-			basemap: InfiniteMap::new(seed),
-			changes: changes.into_iter().collect(),
-			time,
-			modifications: HashSet::new()
+	/// Chunks that fail to save stay dirty so they are saved again later.
+	/// The error of the last chunk that failed is returned
+	pub fn save_chunks(&mut self) -> Result<(), AnyError> {
+		let Some((layer, store)) = &self.store else {
+			return Ok(());
+		};
+		let mut result = Ok(());
+		for (chunk_pos, chunk) in self.chunks.iter_mut() {
+			if chunk.dirty {
+				match store.save_chunk(*layer, *chunk_pos, chunk.save(*chunk_pos)) {
+					Ok(()) => chunk.dirty = false,
+					Err(err) => result = Err(err),
+				}
+			}
+		}
+		result
+	}
+	
+	/// Save and unload the chunks that have not been in a loaded area for a while.
+	/// Chunks that fail to save stay loaded
+	fn unload_inactive(&mut self, areas: &[Area]) -> Result<(), AnyError> {
+		let Some((layer, store)) = &self.store else {
+			return Ok(());
+		};
+		let mut inactive = Vec::new();
+		for (chunk_pos, chunk) in self.chunks.iter_mut() {
+			let chunk_area = Chunk::area(*chunk_pos);
+			if areas.iter().any(|area| area.overlaps(&chunk_area)) {
+				chunk.last_active = self.time;
+			} else if chunk.last_active + TickDuration(UNLOAD_DELAY) < self.time {
+				inactive.push(*chunk_pos);
+			}
+		}
+		let mut result = Ok(());
+		for chunk_pos in inactive {
+			let chunk = &self.chunks[&chunk_pos];
+			if chunk.dirty {
+				if let Err(err) = store.save_chunk(*layer, chunk_pos, chunk.save(chunk_pos)) {
+					result = Err(err);
+					continue;
+				}
+			}
+			if self.chunks.remove(&chunk_pos).unwrap().len() > 0 {
+				self.stored.insert(chunk_pos);
+			}
+		}
+		result
+	}
+	
+	pub fn load(changes: MapSave, basemap: BaseMapEnum, time: Tickstamp) -> Self {
+		let mut map = Self::with_basemap(basemap, time);
+		for (pos, (tile, tickstamp)) in changes {
+			map.insert_change(pos, tile, tickstamp);
 		}
+		map
 	}
 }
 
 pub type MapSave = Vec<(Pos, (Tile, Tickstamp))>;
 
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		crop::Crop,
+		item::Item,
+	};
+	
+	#[test]
+	fn mined_rock_does_not_regrow() {
+		let mut map = Map::new(9876, Tickstamp(0));
+		let (pos, _) = map.load_area(Area::centered(Pos::new(0, 0), Pos::new(256, 256)))
+			.find(|(_, tile)| tile.structure == Structure::Rock)
+			.expect("no rock found around spawn");
+		map.set_structure(pos, Structure::Air);
+		map.time = Tickstamp(randomtick::CHUNK_AREA * 20);
+		let _ = map.load_area(Area::new(pos, Pos::new(1, 1))).count();
+		assert_eq!(map.cell(pos), Tile::ground(Ground::RockFloor));
+	}
+	
+	#[test]
+	fn planted_sapling_grows_into_tree() {
+		let mut map = Map::new(9876, Tickstamp(0));
+		let pos = Pos::new(3, 4);
+		map.set(pos, Tile::structure(Ground::Dirt, Structure::Sapling));
+		map.time = Tickstamp(randomtick::CHUNK_AREA * 6);
+		let _ = map.load_area(Area::new(pos, Pos::new(1, 1))).count();
+		assert_eq!(map.cell(pos), Tile::structure(Ground::Dirt, Structure::Tree));
+	}
+	
+	#[test]
+	fn untended_dirt_near_claim_grows_weeds() {
+		let mut map = Map::new(9876, Tickstamp(0));
+		let claim = Pos::new(10, 10);
+		let near = Pos::new(12, 10);
+		let far = Pos::new(100, 10);
+		map.set(near, Tile::ground(Ground::Dirt));
+		map.set(far, Tile::ground(Ground::Dirt));
+		map.tick(Tickstamp(randomtick::CHUNK_AREA * 2), Vec::new(), vec![claim]);
+		let _ = map.load_area(Area::new(near, Pos::new(1, 1))).count();
+		let _ = map.load_area(Area::new(far, Pos::new(1, 1))).count();
+		assert_eq!(map.cell(near), Tile::structure(Ground::Dirt, Structure::Weeds));
+		assert_ne!(map.cell(far).structure, Structure::Weeds);
+	}
+	
+	#[test]
+	fn channels_carry_water_a_limited_distance() {
+		let mut map = Map::new(9876, Tickstamp(0));
+		let row = |x| Pos::new(x, 7);
+		for x in 0..40 {
+			map.set(row(x), Tile::ground(Ground::Dirt));
+		}
+		map.set_ground(row(0), Ground::Water);
+		for x in 1..40 {
+			map.set_ground(row(x), Ground::Channel);
+		}
+		assert_eq!(map.cell(row(1)).ground, Ground::WetChannel);
+		assert_eq!(map.cell(row(MAX_CHANNEL_FLOW)).ground, Ground::WetChannel);
+		assert_eq!(map.cell(row(MAX_CHANNEL_FLOW + 1)).ground, Ground::Channel);
+		map.set_ground(row(3), Ground::Dirt);
+		assert_eq!(map.cell(row(2)).ground, Ground::WetChannel);
+		assert_eq!(map.cell(row(4)).ground, Ground::Channel);
+	}
+	
+	#[test]
+	fn bog_water_fills_channels() {
+		let mut map = Map::new(9876, Tickstamp(0));
+		let bog = Pos::new(0, 7);
+		let channel = Pos::new(1, 7);
+		map.set(bog, Tile::ground(Ground::BogWater));
+		map.set(channel, Tile::ground(Ground::Dirt));
+		map.set_ground(channel, Ground::Channel);
+		assert_eq!(map.cell(channel).ground, Ground::WetChannel);
+	}
+	
+	#[test]
+	fn irrigated_crops_grow_without_watering() {
+		let mut map = Map::new(9876, Tickstamp(0));
+		let water = Pos::new(0, 7);
+		let channel = Pos::new(1, 7);
+		let crop = Pos::new(1, 8);
+		map.set(water, Tile::ground(Ground::Water));
+		map.set(channel, Tile::ground(Ground::Dirt));
+		map.set_ground(channel, Ground::Channel);
+		map.set(crop, Tile::structure(Ground::Dirt, Structure::Crop(Crop::greenseed())));
+		map.time = Tickstamp(randomtick::CHUNK_AREA * 3);
+		let _ = map.load_area(Area::new(crop, Pos::new(1, 1))).count();
+		assert!(matches!(map.cell(crop).structure, Structure::Crop(c) if c != Crop::greenseed()));
+	}
+	
+	#[test]
+	fn tilled_soil_is_not_restored() {
+		let mut map = Map::new(9876, Tickstamp(0));
+		let pos = Pos::new(3, 4);
+		map.set(pos, Tile::ground(Ground::Tilled(Default::default())));
+		map.time = Tickstamp(randomtick::CHUNK_AREA * 20);
+		let _ = map.load_area(Area::new(pos, Pos::new(1, 1))).count();
+		assert_eq!(map.cell(pos), Tile::ground(Ground::Tilled(Default::default())));
+	}
+	
+	#[test]
+	fn cache_can_only_be_looted_once() {
+		let mut map = Map::new(9876, Tickstamp(0));
+		let (pos, cache) = map.load_area(Area::centered(Pos::new(0, 0), Pos::new(300, 300)))
+			.find(|(_, tile)| tile.structure == Structure::Cache)
+			.expect("no cache found around spawn");
+		assert!(!cache.interact(Item::Hands, map.time, None).unwrap().items.is_empty());
+		map.set_structure(pos, Structure::Air);
+		map.time = Tickstamp(randomtick::CHUNK_AREA * 20);
+		let _ = map.load_area(Area::new(pos, Pos::new(1, 1))).count();
+		assert_eq!(map.cell(pos).structure, Structure::Air);
+	}
+	
+	#[test]
+	fn fire_burns_out_at_exact_time() {
+		let mut map = Map::new(9876, Tickstamp(0));
+		let pos = Pos::new(3, 4);
+		map.set(pos, Tile::structure(Ground::Dirt, Structure::Fire));
+		map.tick(Tickstamp(2999), Vec::new(), Vec::new());
+		assert_eq!(map.cell(pos).structure, Structure::Fire);
+		map.tick(Tickstamp(3000), Vec::new(), Vec::new());
+		assert_eq!(map.cell(pos).structure, Structure::AshPlace);
+	}
+	
+	#[test]
+	fn refueled_fire_ignores_old_timer() {
+		let mut map = Map::new(9876, Tickstamp(0));
+		let pos = Pos::new(3, 4);
+		map.set(pos, Tile::structure(Ground::Dirt, Structure::Fire));
+		map.time = Tickstamp(2000);
+		map.set(pos, Tile::structure(Ground::Dirt, Structure::Fire));
+		let timers = map.timers();
+		let mut restored = Map::new(9876, Tickstamp(2000));
+		restored.set(pos, Tile::structure(Ground::Dirt, Structure::Fire));
+		restored.timers.clear();
+		restored.load_timers(timers);
+		restored.tick(Tickstamp(3000), Vec::new(), Vec::new());
+		assert_eq!(restored.cell(pos).structure, Structure::Fire);
+		restored.tick(Tickstamp(5000), Vec::new(), Vec::new());
+		assert_eq!(restored.cell(pos).structure, Structure::AshPlace);
+	}
+	
+	#[test]
+	fn timer_of_replaced_structure_is_ignored() {
+		let mut map = Map::new(9876, Tickstamp(0));
+		let pos = Pos::new(3, 4);
+		map.set(pos, Tile::structure(Ground::Dirt, Structure::StokedFire));
+		map.set(pos, Tile::structure(Ground::Dirt, Structure::BlazingFire));
+		map.tick(Tickstamp(3000), Vec::new(), Vec::new());
+		assert_eq!(map.cell(pos).structure, Structure::BlazingFire);
+		map.tick(Tickstamp(6000), Vec::new(), Vec::new());
+		assert_eq!(map.cell(pos).structure, Structure::StokedFire);
+	}
+	
+	#[derive(Default)]
+	struct MemoryStore(std::sync::Mutex<HashMap<Pos, crate::chunk::ChunkSave>>);
+	
+	impl ChunkStore for MemoryStore {
+		fn stored_chunks(&self, _layer: Layer) -> Vec<Pos> {
+			self.0.lock().unwrap().keys().copied().collect()
+		}
+		fn load_chunk(&self, _layer: Layer, chunk_pos: Pos) -> Result<crate::chunk::ChunkSave, crate::persistence::LoaderError> {
+			Ok(self.0.lock().unwrap()[&chunk_pos].clone())
+		}
+		fn save_chunk(&self, _layer: Layer, chunk_pos: Pos, save: crate::chunk::ChunkSave) -> Result<(), AnyError> {
+			self.0.lock().unwrap().insert(chunk_pos, save);
+			Ok(())
+		}
+	}
+	
+	#[test]
+	fn inactive_chunks_are_unloaded_and_loaded_again() {
+		let store = Arc::new(MemoryStore::default());
+		let mut map = Map::new(9876, Tickstamp(0));
+		map.set_store(Layer::SURFACE, store.clone());
+		let pos = Pos::new(300, 20);
+		let wall = Tile::structure(Ground::StoneFloor, Structure::Wall);
+		map.set(pos, wall);
+		let elsewhere = Area::centered(Pos::new(0, 0), Pos::new(64, 64));
+		map.tick(Tickstamp(UNLOAD_DELAY * 2), vec![elsewhere], Vec::new());
+		assert!(!map.chunks.contains_key(&(pos / CHUNK_SIZE)));
+		assert!(store.0.lock().unwrap().contains_key(&(pos / CHUNK_SIZE)));
+		assert_eq!(map.cell(pos), wall);
+	}
+	
+	/// A store whose files can't be read and whose disk is full
+	struct BrokenStore(Vec<Pos>);
+	
+	impl ChunkStore for BrokenStore {
+		fn stored_chunks(&self, _layer: Layer) -> Vec<Pos> {
+			self.0.clone()
+		}
+		fn load_chunk(&self, _layer: Layer, _chunk_pos: Pos) -> Result<crate::chunk::ChunkSave, crate::persistence::LoaderError> {
+			Err(crate::persistence::LoaderError::InvalidResource(crate::aerr!("corrupt chunk")))
+		}
+		fn save_chunk(&self, _layer: Layer, _chunk_pos: Pos, _save: crate::chunk::ChunkSave) -> Result<(), AnyError> {
+			Err(crate::aerr!("disk full"))
+		}
+	}
+	
+	#[test]
+	fn store_errors_keep_the_map_running() {
+		let broken = Pos::new(300, 20);
+		let pos = Pos::new(100, 20);
+		let mut map = Map::new(9876, Tickstamp(0));
+		let base = map.cell(broken);
+		map.set_store(Layer::SURFACE, Arc::new(BrokenStore(vec![broken / CHUNK_SIZE])));
+		assert_eq!(map.cell(broken), base);
+		let wall = Tile::structure(Ground::StoneFloor, Structure::Wall);
+		map.set(pos, wall);
+		assert!(map.save_chunks().is_err());
+		let elsewhere = Area::centered(Pos::new(0, 0), Pos::new(64, 64));
+		map.tick(Tickstamp(UNLOAD_DELAY * 2), vec![elsewhere], Vec::new());
+		assert!(map.chunks[&(pos / CHUNK_SIZE)].dirty);
+		assert_eq!(map.cell(pos), wall);
+	}
+	
+	/// A store that keeps chunks as json text, like the chunk files on disk
+	#[derive(Default)]
+	struct TextStore(std::sync::Mutex<HashMap<Pos, String>>);
+	
+	impl ChunkStore for TextStore {
+		fn stored_chunks(&self, _layer: Layer) -> Vec<Pos> {
+			self.0.lock().unwrap().keys().copied().collect()
+		}
+		fn load_chunk(&self, _layer: Layer, chunk_pos: Pos) -> Result<crate::chunk::ChunkSave, crate::persistence::LoaderError> {
+			serde_json::from_str(&self.0.lock().unwrap()[&chunk_pos])
+				.map_err(|err| crate::persistence::LoaderError::InvalidResource(Box::new(err)))
+		}
+		fn save_chunk(&self, _layer: Layer, chunk_pos: Pos, save: crate::chunk::ChunkSave) -> Result<(), AnyError> {
+			self.0.lock().unwrap().insert(chunk_pos, serde_json::to_string(&save)?);
+			Ok(())
+		}
+	}
+	
+	#[test]
+	fn corrupt_chunks_survive_saving() {
+		let pos = Pos::new(300, 20);
+		let corrupt = "[[[300, 20], [{\"ground\": \"Dirt\"".to_string();
+		let store = Arc::new(TextStore::default());
+		store.0.lock().unwrap().insert(pos / CHUNK_SIZE, corrupt.clone());
+		let mut map = Map::new(9876, Tickstamp(0));
+		let base = map.cell(pos);
+		map.set_store(Layer::SURFACE, store.clone());
+		assert_eq!(map.cell(pos), base);
+		assert!(map.changeable(pos).is_err());
+		map.set(pos, Tile::structure(Ground::StoneFloor, Structure::Wall));
+		assert_eq!(map.cell(pos), base);
+		map.save_chunks().unwrap();
+		map.tick(Tickstamp(UNLOAD_DELAY * 2), Vec::new(), Vec::new());
+		assert_eq!(store.0.lock().unwrap()[&(pos / CHUNK_SIZE)], corrupt);
+	}
+	
+	#[test]
+	fn spreading_is_capped_per_chunk() {
+		let weedy_map = |filler: Tile, chunk_changes: usize| {
+			let mut map = Map::new(9876, Tickstamp(0));
+			let pos = Pos::new(3, 4);
+			map.claims = vec![pos];
+			map.set(pos, Tile::structure(Ground::Dirt, Structure::Weeds));
+			for d in Direction::DIRECTIONS {
+				map.set(pos + d, Tile::ground(Ground::Dirt));
+			}
+			for i in 0..chunk_changes as i32 {
+				map.set(Pos::new(i % CHUNK_SIZE, 20 + i / CHUNK_SIZE), filler);
+			}
+			for t in 0..100 {
+				map.time = Tickstamp(t);
+				map.spread(pos);
+			}
+			Direction::DIRECTIONS.iter().filter(|d| map.cell(pos + **d).structure == Structure::Weeds).count()
+		};
+		let wall = Tile::structure(Ground::StoneFloor, Structure::Wall);
+		let weeds = Tile::structure(Ground::Dirt, Structure::Weeds);
+		assert!(weedy_map(wall, 0) > 0);
+		assert!(weedy_map(wall, MAX_SPREAD_CHANGES) > 0);
+		assert_eq!(weedy_map(weeds, MAX_SPREAD_CHANGES), 0);
+	}
+	
+	#[test]
+	fn wild_plants_spread_only_in_their_habitat() {
+		let spread_count = |structure: Structure, ground: Ground, claims: Vec<Pos>| {
+			let mut map = Map::new(9876, Tickstamp(0));
+			map.claims = claims;
+			let pos = Pos::new(3, 4);
+			let area = Area::centered(pos, Pos::new(5, 5));
+			for p in area.iter() {
+				map.set(p, Tile::ground(ground));
+			}
+			for p in Area::centered(pos, Pos::new(3, 3)).iter() {
+				map.set(p, Tile::ground(Ground::Dirt));
+			}
+			map.set(pos, Tile::structure(Ground::Dirt, structure));
+			for t in 0..100 {
+				map.time = Tickstamp(t);
+				map.spread(pos);
+			}
+			area.iter().filter(|p| *p != pos && map.cell(*p).structure == structure).count()
+		};
+		assert!(spread_count(Structure::Reed, Ground::Water, Vec::new()) > 0);
+		assert_eq!(spread_count(Structure::Reed, Ground::StoneFloor, Vec::new()), 0);
+		assert!(spread_count(Structure::Weeds, Ground::StoneFloor, vec![Pos::new(0, 0)]) > 0);
+		assert_eq!(spread_count(Structure::Weeds, Ground::StoneFloor, vec![Pos::new(500, 0)]), 0);
+	}
+}
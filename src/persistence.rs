@@ -5,6 +5,8 @@ use std::env;
 use std::io::ErrorKind;
 use crate::{
 	PlayerId,
+	pos::{Pos, Layer},
+	chunk::{ChunkStore, ChunkSave},
 	world::WorldSave,
 	creature::PlayerSave,
 	errors::AnyError,
@@ -34,12 +36,14 @@ pub trait PersistentStorage {
 	
 	fn load_world(&self) -> Result<WorldSave, LoaderError>;
 	fn load_player(&self, id: &PlayerId) -> Result<PlayerSave, LoaderError>;
+	fn list_players(&self) -> Vec<PlayerId>;
 	
 	fn save_world(&self, state: WorldSave) -> Result<(), AnyError>;
 	fn save_player(&self, id: &PlayerId, state: PlayerSave) -> Result<(), AnyError>;
 }
 
 
+#[derive(Debug, Clone)]
 pub struct FileStorage {
 	directory: PathBuf
 }
@@ -105,6 +109,20 @@ impl PersistentStorage for FileStorage {
 		Ok(state)
 	}
 	
+	fn list_players(&self) -> Vec<PlayerId> {
+		let mut path = self.directory.clone();
+		path.push("players");
+		let Ok(entries) = fs::read_dir(path) else {
+			return Vec::new();
+		};
+		entries
+			.filter_map(|entry| {
+				let name = entry.ok()?.file_name().into_string().ok()?;
+				Some(PlayerId(name.strip_suffix(".save.json")?.to_string()))
+			})
+			.collect()
+	}
+	
 	
 	fn save_world(&self, state: WorldSave) -> Result<(), AnyError> {
 		let mut path = self.directory.clone();
@@ -128,4 +146,50 @@ impl PersistentStorage for FileStorage {
 	
 }
 
+impl FileStorage {
+	fn chunk_dir(&self, layer: Layer) -> PathBuf {
+		let mut path = self.directory.clone();
+		path.push("chunks");
+		path.push(layer.0.to_string());
+		path
+	}
+}
+
+impl ChunkStore for FileStorage {
+
+	fn stored_chunks(&self, layer: Layer) -> Vec<Pos> {
+		let Ok(entries) = fs::read_dir(self.chunk_dir(layer)) else {
+			return Vec::new();
+		};
+		entries
+			.filter_map(|entry| {
+				let name = entry.ok()?.file_name().into_string().ok()?;
+				let (x, y) = name.strip_suffix(".chunk.json")?.split_once('_')?;
+				Some(Pos::new(x.parse().ok()?, y.parse().ok()?))
+			})
+			.collect()
+	}
+
+	fn load_chunk(&self, layer: Layer, chunk_pos: Pos) -> Result<ChunkSave, LoaderError> {
+		let mut path = self.chunk_dir(layer);
+		path.push(format!("{}_{}.chunk.json", chunk_pos.x, chunk_pos.y));
+		let text = fs::read_to_string(path).map_err(|err| {
+			if err.kind() == ErrorKind::NotFound {
+				LoaderError::MissingResource(Box::new(err))
+			} else {
+				LoaderError::InvalidResource(Box::new(err))
+			}
+		})?;
+		let state = inv!(serde_json::from_str(&text))?;
+		Ok(state)
+	}
 
+	fn save_chunk(&self, layer: Layer, chunk_pos: Pos, save: ChunkSave) -> Result<(), AnyError> {
+		let mut path = self.chunk_dir(layer);
+		fs::create_dir_all(&path)?;
+		path.push(format!("{}_{}.chunk.json", chunk_pos.x, chunk_pos.y));
+		let text = serde_json::to_string(&save).unwrap();
+		write_file_safe(path, text)?;
+		Ok(())
+	}
+}
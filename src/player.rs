@@ -3,9 +3,10 @@ use std::fmt;
 use serde::{Serialize, Deserialize};
 
 use crate::{
-	pos::Area,
+	pos::{Pos, Area, Layer},
 	controls::Control,
 	creature::CreatureId,
+	basemap::Biome,
 	vec2::Vec2,
 };
 
@@ -24,7 +25,17 @@ pub struct Player {
 	pub movement: Option<Vec2>,
 	pub body: CreatureId,
 	pub is_new: bool,
-	pub view_area: Option<Area>
+	pub view_area: Option<Area>,
+	pub view_layer: Layer,
+	pub compass: Option<Compass>
+}
+
+/// The nearest biomes as seen from the biome cell with this core on this layer
+#[derive(Debug, Clone)]
+pub struct Compass {
+	pub layer: Layer,
+	pub core: Pos,
+	pub nearest: Vec<(Biome, Pos)>,
 }
 
 
@@ -36,7 +47,9 @@ impl Player {
 			movement: None,
 			body,
 			is_new: true,
-			view_area: None
+			view_area: None,
+			view_layer: Layer::SURFACE,
+			compass: None
 		}
 	}
 
@@ -0,0 +1,93 @@
+
+use enum_assoc::Assoc;
+use crate::{
+	pos::Pos,
+	tile::{Tile, Ground, Structure},
+};
+
+macro_rules! t {
+	($g:ident) => {Tile::ground(Ground::$g)};
+	($g:ident, $s:ident) => {Tile::structure(Ground::$g, Structure::$s)};
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Assoc)]
+#[func(fn template(&self) -> &'static [&'static str])]
+pub enum Poi {
+	#[assoc(template = &[
+		"#%## ,##",
+		"#......#",
+		"%..,...%",
+		" ...$.. ",
+		"#.,....#",
+		"##.  %##",
+	])]
+	Ruin,
+
+	#[assoc(template = &[
+		"=== ==w=",
+		"=dd_kkw=",
+		"=w______",
+		"=hh_dd_=",
+		"==w= ===",
+	])]
+	Garden,
+
+	#[assoc(template = &[
+		" , ",
+		",$%",
+		" , ",
+	])]
+	Cache,
+}
+
+impl Poi {
+	/// The tile of this point of interest at dpos relative to its center, if it changes the tile there
+	pub fn tile(&self, dpos: Pos) -> Option<Tile> {
+		let template = self.template();
+		let height = template.len() as i32;
+		let width = template[0].len() as i32;
+		let pos = dpos + Pos::new(width / 2, height / 2);
+		if pos.x < 0 || pos.y < 0 || pos.x >= width || pos.y >= height {
+			return None;
+		}
+		legend(template[pos.y as usize].as_bytes()[pos.x as usize])
+	}
+}
+
+fn legend(c: u8) -> Option<Tile> {
+	Some(match c {
+		b'#' => t!(StoneFloor, Wall),
+		b'%' => t!(StoneFloor, Stone),
+		b'.' => t!(StoneFloor),
+		b',' => t!(RockFloor, Gravel),
+		b'$' => t!(StoneFloor, Cache),
+		b'=' => t!(Dirt, WoodWall),
+		b'_' => t!(Dirt),
+		b'w' => t!(Dirt, Weeds),
+		b'd' => t!(Dirt, SeedingDiscLeaf),
+		b'k' => t!(Dirt, SeedingKnifeLeaf),
+		b'h' => t!(Dirt, SeedingHardwood),
+		_ => return None
+	})
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn templates_are_rectangular() {
+		for poi in [Poi::Ruin, Poi::Garden, Poi::Cache] {
+			let width = poi.template()[0].len();
+			assert!(poi.template().iter().all(|row| row.len() == width), "{:?} template is not rectangular", poi);
+		}
+	}
+
+	#[test]
+	fn cache_is_at_center() {
+		assert_eq!(Poi::Cache.tile(Pos::new(0, 0)), Some(t!(StoneFloor, Cache)));
+		assert_eq!(Poi::Cache.tile(Pos::new(-1, -1)), None);
+		assert_eq!(Poi::Cache.tile(Pos::new(5, 0)), None);
+	}
+}
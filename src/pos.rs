@@ -1,7 +1,12 @@
 
 
 use std::ops::{Add, Sub, Neg, Mul, Div, Rem, AddAssign};
+use std::str::FromStr;
 use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use crate::{
+	err,
+	errors::AError
+};
 
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -29,6 +34,16 @@ impl Direction {
 }
 
 
+/// Vertical level of a map. 0 is the surface, negative layers are underground.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, Default, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Layer(pub i32);
+
+impl Layer {
+	pub const SURFACE: Layer = Layer(0);
+	pub const UNDERGROUND: Layer = Layer(-1);
+}
+
+
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, Default)]
 pub struct Pos {
 	pub x: i32,
@@ -77,7 +92,6 @@ impl Pos {
 		self.x.abs() + self.y.abs()
 	}
 	
-	#[allow(dead_code)]
 	pub fn is_zero(&self) -> bool {
 		self.x == 0 && self.y == 0
 	}
@@ -275,6 +289,17 @@ impl Area {
 			&& pos.y < self.max().y
 	}
 
+	/// Split the area into parts of at most size, in row order starting from the minimum corner
+	pub fn blocks(&self, size: Pos) -> impl Iterator<Item=Area> {
+		let area = *self;
+		let count = Pos::new((area.size.x + size.x - 1) / size.x, (area.size.y + size.y - 1) / size.y);
+		Area::new(Pos::zero(), count).iter()
+			.map(move |block| {
+				let min = area.min + Pos::new(block.x * size.x, block.y * size.y);
+				Area::between(min, Pos::new((min.x + size.x).min(area.max().x), (min.y + size.y).min(area.max().y)))
+			})
+	}
+
 	pub fn overlaps(&self, other: &Area) -> bool {
 		! (self.min().x >= other.max().x || self.max().x <= other.min().x || self.min().y >= other.max().y || self.max().y <= other.min().y)
 	}
@@ -300,6 +325,19 @@ impl<'de> Deserialize<'de> for Area {
 	}
 }
 
+impl FromStr for Area {
+	type Err = AError;
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let parts: Vec<i32> = s.split(',')
+			.map(|part| part.trim().parse().map_err(|e| err!("'{}' is not a valid number: {}", part, e)))
+			.collect::<Result<_, _>>()?;
+		match parts[..] {
+			[x, y, w, h] if w >= 0 && h >= 0 => Ok(Self::new(Pos::new(x, y), Pos::new(w, h))),
+			_ => Err(err!("Area should be 'x,y,width,height' with non-negative size, not {:?}", s))
+		}
+	}
+}
+
 pub struct AreaIter{
 	area: Area,
 	x: i32,
@@ -347,6 +385,20 @@ mod tests {
 		assert_eq!(area, Area::new(Pos::new(8, 17), Pos::new(4, 6)));
 	}
 	#[test]
+	fn parses_area(){
+		assert_eq!("-10,5,20,30".parse::<Area>().unwrap(), Area::new(Pos::new(-10, 5), Pos::new(20, 30)));
+		assert!("1,2,3".parse::<Area>().is_err());
+		assert!("1,2,-3,4".parse::<Area>().is_err());
+	}
+	#[test]
+	fn blocks_cover_area(){
+		let area = Area::new(Pos::new(-5, 3), Pos::new(10, 7));
+		let blocks: Vec<Area> = area.blocks(Pos::new(4, 4)).collect();
+		assert_eq!(blocks.len(), 6);
+		assert_eq!(blocks[5], Area::new(Pos::new(3, 7), Pos::new(2, 3)));
+		assert_eq!(blocks.iter().map(Area::surface).sum::<i32>(), area.surface());
+	}
+	#[test]
 	fn iterates_over_zero_width_area(){
 		let area = Area::new(Pos::new(10, 10), Pos::new(0, 10));
 		assert_eq!(area.iter().next(), None);
@@ -0,0 +1,218 @@
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use crate::{
+	pos::{Pos, Area},
+	tile::{Tile, Ground, Structure},
+	map::Map,
+	grid::Grid,
+	errors::Result,
+};
+
+/// Tiles are loaded in blocks of this size so large areas don't have to be generated at once
+const BLOCK_SIZE: i32 = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Marker {
+	Claim,
+	Player,
+}
+
+impl Marker {
+	fn color(&self) -> [u8; 3] {
+		match self {
+			Marker::Claim => [255, 0, 255],
+			Marker::Player => [255, 0, 0],
+		}
+	}
+
+	fn symbol(&self) -> char {
+		match self {
+			Marker::Claim => 'C',
+			Marker::Player => '@',
+		}
+	}
+}
+
+/// A top-down view of an area of a map, with one pixel or character for every `scale` by `scale` tiles
+pub struct MapImage {
+	area: Area,
+	scale: i32,
+	tiles: Grid<Tile>,
+	markers: Vec<(Pos, Marker)>,
+}
+
+impl MapImage {
+	pub fn new(map: &mut Map, area: Area, scale: i32) -> Self {
+		let size = (area.size() + Pos::new(scale - 1, scale - 1)) / scale;
+		let mut tiles = Grid::new(Area::new(Pos::zero(), size), Tile::default());
+		for block in area.blocks(Pos::new(BLOCK_SIZE, BLOCK_SIZE)) {
+			for (pos, tile) in map.load_area(block) {
+				let offset = pos - area.min();
+				if (offset % scale).is_zero() {
+					tiles.set(offset / scale, tile);
+				}
+			}
+			// loading marks every tile as modified; nobody is watching those here
+			map.flush();
+		}
+		Self {
+			area,
+			scale,
+			tiles,
+			markers: Vec::new(),
+		}
+	}
+
+	pub fn mark(&mut self, pos: Pos, marker: Marker) {
+		if self.area.contains(pos) {
+			self.markers.push(((pos - self.area.min()) / self.scale, marker));
+		}
+	}
+
+	pub fn to_ascii(&self) -> String {
+		let size = self.tiles.area.size();
+		let mut rows: Vec<Vec<char>> = (0..size.y)
+			.map(|y| (0..size.x).map(|x| tile_symbol(self.tiles.get(Pos::new(x, y)).unwrap())).collect())
+			.collect();
+		for (pos, marker) in self.markers.iter() {
+			rows[pos.y as usize][pos.x as usize] = marker.symbol();
+		}
+		rows.into_iter()
+			.map(|row| row.into_iter().collect::<String>() + "\n")
+			.collect()
+	}
+
+	pub fn to_png(&self, path: &Path) -> Result<()> {
+		let size = self.tiles.area.size();
+		let mut pixels: Grid<[u8; 3]> = Grid::new(self.tiles.area, [0, 0, 0]);
+		for pos in self.tiles.area.iter() {
+			pixels.set(pos, tile_color(self.tiles.get(pos).unwrap()));
+		}
+		// markers get a 3x3 square so they stay visible on large maps
+		for (pos, marker) in self.markers.iter() {
+			for p in Area::centered(*pos, Pos::new(3, 3)).iter() {
+				pixels.set(p, marker.color());
+			}
+		}
+		let data: Vec<u8> = self.tiles.area.iter()
+			.flat_map(|pos| *pixels.get(pos).unwrap())
+			.collect();
+		let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), size.x as u32, size.y as u32);
+		encoder.set_color(png::ColorType::Rgb);
+		encoder.set_depth(png::BitDepth::Eight);
+		encoder.write_header()?.write_image_data(&data)?;
+		Ok(())
+	}
+}
+
+fn tile_color(tile: &Tile) -> [u8; 3] {
+	structure_color(tile.structure).unwrap_or_else(|| ground_color(tile.ground))
+}
+
+fn ground_color(ground: Ground) -> [u8; 3] {
+	match ground {
+		Ground::Dirt => [134, 96, 67],
+		Ground::Tilled(_) => [110, 75, 50],
+		Ground::Grass1 => [104, 170, 64],
+		Ground::Grass2 => [92, 160, 56],
+		Ground::Grass3 => [80, 150, 50],
+		Ground::Moss => [60, 120, 64],
+		Ground::DeadLeaves => [140, 110, 60],
+		Ground::Sanctuary => [200, 190, 140],
+		Ground::Water => [40, 90, 200],
+		Ground::BogWater => [60, 90, 90],
+		Ground::Channel => [120, 90, 60],
+		Ground::WetChannel => [70, 100, 170],
+		Ground::RockFloor => [120, 120, 120],
+		Ground::StoneFloor => [150, 150, 150],
+		Ground::WoodFloor => [160, 120, 70],
+		Ground::Sand => [220, 200, 140],
+		Ground::Snow => [240, 240, 250],
+		Ground::Ice => [180, 210, 240],
+		Ground::Empty => [0, 0, 0],
+	}
+}
+
+/// Only larger structures get their own color; small ones show the ground below
+fn structure_color(structure: Structure) -> Option<[u8; 3]> {
+	Some(match structure {
+		Structure::Wall => [100, 100, 100],
+		Structure::WoodWall | Structure::PlankWall => [110, 80, 50],
+		Structure::Rock => [90, 90, 90],
+		Structure::RockMid => [60, 60, 60],
+		Structure::CopperOre => [180, 100, 60],
+		Structure::CaveEntrance | Structure::CaveExit => [20, 20, 20],
+		Structure::Sapling | Structure::YoungTree => [50, 120, 40],
+		Structure::Tree | Structure::OldTree | Structure::OldTreeTinder => [30, 90, 30],
+		Structure::PineTree => [20, 70, 40],
+		Structure::Cactus => [100, 150, 60],
+		Structure::Shrub | Structure::Bush => [60, 110, 50],
+		Structure::Reed | Structure::Rush => [150, 160, 80],
+		Structure::Fire | Structure::StokedFire | Structure::BlazingFire => [230, 110, 30],
+		Structure::MarkStone | Structure::MarkerAltar => [200, 200, 220],
+		_ => return None,
+	})
+}
+
+fn tile_symbol(tile: &Tile) -> char {
+	match tile.structure {
+		Structure::Air => {},
+		Structure::Wall | Structure::WoodWall | Structure::PlankWall => return '#',
+		Structure::Rock | Structure::RockMid => return 'A',
+		Structure::CopperOre => return '$',
+		Structure::CaveEntrance | Structure::CaveExit => return 'O',
+		Structure::Sapling | Structure::YoungTree => return 't',
+		Structure::Tree | Structure::OldTree | Structure::OldTreeTinder | Structure::PineTree => return 'T',
+		Structure::Cactus => return 'Y',
+		Structure::Fire | Structure::StokedFire | Structure::BlazingFire => return '^',
+		Structure::MarkStone | Structure::MarkerAltar => return 'M',
+		_ => return '*',
+	}
+	match tile.ground {
+		Ground::Water | Ground::WetChannel => '~',
+		Ground::BogWater => '%',
+		Ground::Ice => '=',
+		Ground::Snow => '-',
+		Ground::Sand => ':',
+		Ground::Grass1 | Ground::Grass2 | Ground::Grass3 | Ground::Moss => '"',
+		Ground::Dirt | Ground::Tilled(_) | Ground::Channel | Ground::DeadLeaves => '.',
+		Ground::RockFloor | Ground::StoneFloor | Ground::WoodFloor | Ground::Sanctuary => '_',
+		Ground::Empty => ' ',
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		basemap::BaseMapEnum,
+		fixedmap::{FixedMap, FixedMapTemplate},
+		tickstamp::Tickstamp,
+	};
+
+	#[test]
+	fn ascii_render_samples_tiles_and_markers() {
+		let template = FixedMapTemplate::parse(r#"{
+			spawn: [0, 0],
+			outside: ["Water", "Air"],
+			legend: {
+				"X": ["StoneFloor", "Wall"],
+				".": ["Dirt", "Air"],
+			},
+			map: [
+				"X.X.",
+				"....",
+				"X.X.",
+			],
+		}"#).unwrap();
+		let mut map = Map::with_basemap(BaseMapEnum::FixedMap(FixedMap::new(&template)), Tickstamp(0));
+		let mut image = MapImage::new(&mut map, Area::new(Pos::new(0, 0), Pos::new(6, 3)), 2);
+		assert!(map.modified().is_empty());
+		image.mark(Pos::new(3, 2), Marker::Player);
+		image.mark(Pos::new(10, 10), Marker::Claim);
+		assert_eq!(image.to_ascii(), "##~\n#@~\n");
+	}
+}
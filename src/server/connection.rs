@@ -2,6 +2,7 @@
 use std::io;
 use std::io::{Read, Write};
 use tungstenite::{WebSocket, Message};
+use super::Encoding;
 
 
 #[derive(Debug)]
@@ -9,6 +10,7 @@ pub enum ConnectionError {
 	IO(io::Error),
 	Tungstenite(tungstenite::Error),
 	Custom(String),
+	Encoding(String),
 	UnknownProtocol
 }
 
@@ -16,25 +18,29 @@ pub trait Connection<T: Read+Write>: Sized {
 
 	fn new(stream: T) -> Result<Self, ConnectionError>;
 	
-	fn read(&mut self) -> Result<(Vec<String>, bool), ConnectionError>;
+	fn read(&mut self) -> Result<(Vec<Vec<u8>>, bool), ConnectionError>;
 	
-	fn send(&mut self, text: &str) -> Result<(), ConnectionError>;
+	fn send(&mut self, data: &[u8]) -> Result<(), ConnectionError>;
+
+	/// The encoding that was negotiated from the first message of the client
+	fn encoding(&self) -> Encoding;
 
 }
 
 
 pub struct StreamConnection<T: Read+Write> {
 	stream: T,
-	buffer: Vec<u8>
+	buffer: Vec<u8>,
+	encoding: Option<Encoding>
 }
 
 impl <T: Read+Write>Connection<T> for StreamConnection<T> {
 
 	fn new(stream: T) -> Result<Self, ConnectionError> {
-		Ok(Self { stream, buffer: Vec::new()})
+		Ok(Self { stream, buffer: Vec::new(), encoding: None})
 	}
 	
-	fn read(&mut self) -> Result<(Vec<String>, bool), ConnectionError> {
+	fn read(&mut self) -> Result<(Vec<Vec<u8>>, bool), ConnectionError> {
 		let mut buf = [0; 2048];
 		let mut closed = false;
 		loop {
@@ -64,25 +70,30 @@ impl <T: Read+Write>Connection<T> for StreamConnection<T> {
 				break;
 			}
 			let rest = self.buffer.split_off(4+mlen);
-			let message = String::from_utf8_lossy(&self.buffer[4..]).to_string();
+			let message = self.buffer.split_off(4);
+			self.encoding.get_or_insert_with(|| Encoding::detect(&message));
 			messages.push(message);
 			self.buffer = rest;
 		}
 		Ok((messages, closed))
 	}
 	
-	fn send(&mut self, text: &str) -> Result<(), ConnectionError> {
-		let bytes: &[u8] = text.as_bytes();
-		let len: u32 = bytes.len() as u32;
+	fn send(&mut self, data: &[u8]) -> Result<(), ConnectionError> {
+		let len: u32 = data.len() as u32;
 		let header: [u8; 4] = len.to_be_bytes();
 		self.stream.write_all(&header).map_err(ConnectionError::IO)?;
-		self.stream.write_all(bytes).map_err(ConnectionError::IO)?;
+		self.stream.write_all(data).map_err(ConnectionError::IO)?;
 		Ok(())
 	}
+
+	fn encoding(&self) -> Encoding {
+		self.encoding.unwrap_or_default()
+	}
 }
 
 pub struct WebSocketConnection<T: Read+Write> {
-	websocket: WebSocket<T>
+	websocket: WebSocket<T>,
+	encoding: Option<Encoding>
 }
 
 fn is_wouldblock_error(error: &tungstenite::Error) -> bool {
@@ -105,10 +116,10 @@ impl <T: Read+Write>Connection<T> for WebSocketConnection<T> {
 				return Err(ConnectionError::Tungstenite(err));
 			}
 		};
-		Ok(Self { websocket })
+		Ok(Self { websocket, encoding: None })
 	}
 	
-	fn read(&mut self) -> Result<(Vec<String>, bool), ConnectionError> {
+	fn read(&mut self) -> Result<(Vec<Vec<u8>>, bool), ConnectionError> {
 		let mut messages = Vec::new();
 		let mut is_closed = false;
 		loop {
@@ -122,7 +133,12 @@ impl <T: Read+Write>Connection<T> for WebSocketConnection<T> {
 				}
 				Ok(Message::Text(text)) => {
 					// println!("websocket text: {}", text.clone());
-					messages.push(text);
+					self.encoding.get_or_insert(Encoding::Json);
+					messages.push(text.into_bytes());
+				}
+				Ok(Message::Binary(data)) => {
+					self.encoding.get_or_insert(Encoding::MessagePack);
+					messages.push(data);
 				}
 				Ok(Message::Close(_)) => {
 					// println!("websocket close");
@@ -136,10 +152,18 @@ impl <T: Read+Write>Connection<T> for WebSocketConnection<T> {
 		Ok((messages, is_closed))
 	}
 	
-	fn send(&mut self, text: &str) -> Result<(), ConnectionError> {
-		self.websocket.write_message(Message::Text(text.to_string()))
+	fn send(&mut self, data: &[u8]) -> Result<(), ConnectionError> {
+		let message = match self.encoding() {
+			Encoding::Json => Message::Text(String::from_utf8_lossy(data).to_string()),
+			Encoding::MessagePack => Message::Binary(data.to_vec()),
+		};
+		self.websocket.write_message(message)
 			.map_err(ConnectionError::Tungstenite)
 	}
+
+	fn encoding(&self) -> Encoding {
+		self.encoding.unwrap_or_default()
+	}
 }
 
 pub trait Peek {
@@ -195,7 +219,7 @@ impl <T: Read+Write+Peek>Connection<T> for DynCon<T> {
 		Ok(Self::Unknown(stream))
 	}
 	
-	fn read(&mut self) -> Result<(Vec<String>, bool), ConnectionError> {
+	fn read(&mut self) -> Result<(Vec<Vec<u8>>, bool), ConnectionError> {
 		match self {
 			Self::Web(conn) => conn.read(),
 			Self::TCon(conn) => conn.read(),
@@ -210,17 +234,25 @@ impl <T: Read+Write+Peek>Connection<T> for DynCon<T> {
 	}
 	
 	
-	fn send(&mut self, text: &str) -> Result<(), ConnectionError> {
+	fn send(&mut self, data: &[u8]) -> Result<(), ConnectionError> {
 		
 		match self {
 			Self::Web(conn) =>
-				conn.send(text),
+				conn.send(data),
 			Self::TCon(conn) =>
-				conn.send(text),
+				conn.send(data),
 			Self::Unknown(_conn) => 
 				Err(ConnectionError::UnknownProtocol),
 			Self::Invalid =>
 				Err(ConnectionError::Custom("Tried to send to invalid connection".to_string()))
 		}
 	}
+
+	fn encoding(&self) -> Encoding {
+		match self {
+			Self::Web(conn) => conn.encoding(),
+			Self::TCon(conn) => conn.encoding(),
+			Self::Unknown(_) | Self::Invalid => Encoding::default()
+		}
+	}
 }
@@ -0,0 +1,93 @@
+
+use serde::{Serialize, de::DeserializeOwned};
+use super::ConnectionError;
+
+/// The format of the messages on a connection.
+/// A connection uses the format of the first message its client sends
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+	#[default]
+	Json,
+	MessagePack,
+}
+
+impl Encoding {
+	/// JSON messages start with text, while MessagePack maps and arrays start with a byte of 0x80 or higher
+	pub fn detect(data: &[u8]) -> Self {
+		match data.first() {
+			Some(0x80..=0x9f | 0xdc..=0xdf) => Self::MessagePack,
+			_ => Self::Json
+		}
+	}
+
+	pub fn encode<T: Serialize + ?Sized>(&self, value: &T) -> Result<Vec<u8>, ConnectionError> {
+		match self {
+			Self::Json => serde_json::to_vec(value)
+				.map_err(|err| ConnectionError::Encoding(err.to_string())),
+			Self::MessagePack => rmp_serde::to_vec_named(value)
+				.map_err(|err| ConnectionError::Encoding(err.to_string())),
+		}
+	}
+
+	pub fn decode<T: DeserializeOwned>(&self, data: &[u8]) -> Result<T, ConnectionError> {
+		match self {
+			Self::Json => serde_json::from_slice(data)
+				.map_err(|err| ConnectionError::Encoding(err.to_string())),
+			Self::MessagePack => rmp_serde::from_slice(data)
+				.map_err(|err| ConnectionError::Encoding(err.to_string())),
+		}
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::{Value, json};
+
+	#[test]
+	fn detects_encoding_from_first_byte() {
+		let value = json!({"chat": "hello"});
+		assert_eq!(Encoding::detect(&Encoding::Json.encode(&value).unwrap()), Encoding::Json);
+		assert_eq!(Encoding::detect(&Encoding::MessagePack.encode(&value).unwrap()), Encoding::MessagePack);
+		assert_eq!(Encoding::detect(b" [1, 2]"), Encoding::Json);
+	}
+
+	#[test]
+	fn values_round_trip() {
+		let value = json!(["error", "invalidname", "A name must have at least one character"]);
+		for encoding in [Encoding::Json, Encoding::MessagePack] {
+			let data = encoding.encode(&value).unwrap();
+			assert_eq!(encoding.decode::<Value>(&data).unwrap(), value);
+		}
+	}
+
+	/// The messages that the game server sends besides world messages, as the web client reads them
+	fn assert_decodes_to(data: &[u8], encoding: Encoding, expected: Value) {
+		assert_eq!(encoding.decode::<Value>(data).unwrap(), expected, "{:?}", encoding);
+	}
+
+	#[test]
+	fn connected_message_is_encoded() {
+		for encoding in [Encoding::Json, Encoding::MessagePack] {
+			let data = encoding.encode(&("connected", "successfully connected as alice")).unwrap();
+			assert_decodes_to(&data, encoding, json!(["connected", "successfully connected as alice"]));
+		}
+	}
+
+	#[test]
+	fn error_message_is_encoded() {
+		for encoding in [Encoding::Json, Encoding::MessagePack] {
+			let data = encoding.encode(&("error", "nametaken", "Another connection to this player exists already")).unwrap();
+			assert_decodes_to(&data, encoding, json!(["error", "nametaken", "Another connection to this player exists already"]));
+		}
+	}
+
+	#[test]
+	fn chat_message_is_encoded() {
+		for encoding in [Encoding::Json, Encoding::MessagePack] {
+			let data = encoding.encode(&("message", "alice: hello", "")).unwrap();
+			assert_decodes_to(&data, encoding, json!(["message", "alice: hello", ""]));
+		}
+	}
+}
@@ -2,6 +2,7 @@ mod tcpserver;
 mod unixserver;
 mod address;
 mod connection;
+mod encoding;
 
 use enum_dispatch::enum_dispatch;
 
@@ -9,6 +10,7 @@ use crate::util::HolderId;
 use unixserver::UnixServer;
 pub use address::Address;
 pub use connection::ConnectionError;
+pub use encoding::Encoding;
 
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -22,7 +24,8 @@ impl HolderId for ConnectionId {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Message {
 	pub connection: ConnectionId,
-	pub content: String
+	pub content: Vec<u8>,
+	pub encoding: Encoding
 }
 
 #[derive(Debug, Clone)]
@@ -46,9 +49,11 @@ pub trait Server {
 	
 	fn recv_pending_messages(&mut self) -> MessageUpdates;
 	
-	fn send(&mut self, id: ConnectionId, text: &str) -> Result<(), ServerError>;
+	fn send(&mut self, id: ConnectionId, data: &[u8]) -> Result<(), ServerError>;
 	
-	fn broadcast(&mut self, text: &str);
+	fn broadcast(&mut self, data: &[u8]);
+
+	fn encoding(&self, id: ConnectionId) -> Option<Encoding>;
 	
 	fn get_name(&self, _id: ConnectionId) -> Option<String> {
 		None
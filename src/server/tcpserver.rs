@@ -11,6 +11,7 @@ use super::{
 	ConnectionId,
 	Message,
 	MessageUpdates,
+	Encoding,
 	ServerError
 };
 
@@ -54,7 +55,7 @@ impl <T: Connection<TcpStream>> Server for TcpServer<T> {
 				}
 				Ok((con_messages, closed)) => {
 					for message in con_messages {
-						messages.push(Message{connection: *connection_id, content: message});
+						messages.push(Message{connection: *connection_id, content: message, encoding: connection.encoding()});
 					}
 					if closed {
 						to_remove.push(*connection_id);
@@ -68,20 +69,25 @@ impl <T: Connection<TcpStream>> Server for TcpServer<T> {
 		MessageUpdates{messages, to_remove}
 	}
 
-	fn broadcast(&mut self, text: &str) {
+	fn broadcast(&mut self, data: &[u8]) {
 		for (_id, conn) in self.connections.iter_mut() {
-			let _ = conn.send(text);
+			let _ = conn.send(data);
 		}
 	}
 	
-	fn send(&mut self, id: ConnectionId, text: &str) -> Result<(), ServerError> {
+	fn send(&mut self, id: ConnectionId, data: &[u8]) -> Result<(), ServerError> {
 		match self.connections.get_mut(&id){
 			Some(conn) => {
-				conn.send(text).map_err(ServerError::Connection)
+				conn.send(data).map_err(ServerError::Connection)
 			}
 			None => Err(ServerError::InvalidIndex(id))
 		}
 	}
+
+	fn encoding(&self, id: ConnectionId) -> Option<Encoding> {
+		let conn = self.connections.get(&id)?;
+		Some(conn.encoding())
+	}
 	
 
 }
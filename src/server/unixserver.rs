@@ -14,6 +14,7 @@ use super::{
 	ConnectionId,
 	Message,
 	MessageUpdates,
+	Encoding,
 	ServerError
 };
 
@@ -60,7 +61,7 @@ impl Server for UnixServer {
 				}
 				Ok((con_messages, closed)) => {
 					for message in con_messages {
-						messages.push(Message{connection: *connection_id, content: message})
+						messages.push(Message{connection: *connection_id, content: message, encoding: connection.encoding()})
 					}
 					if closed {
 						to_remove.push(*connection_id);
@@ -74,20 +75,25 @@ impl Server for UnixServer {
 		MessageUpdates{messages, to_remove}
 	}
 
-	fn broadcast(&mut self, text: &str) {
+	fn broadcast(&mut self, data: &[u8]) {
 		for (_id, (conn, _fd)) in self.connections.iter_mut() {
-			let _ = conn.send(text);
+			let _ = conn.send(data);
 		}
 	}
 	
-	fn send(&mut self, id: ConnectionId, text: &str) -> Result<(), ServerError> {
+	fn send(&mut self, id: ConnectionId, data: &[u8]) -> Result<(), ServerError> {
 		match self.connections.get_mut(&id){
 			Some((conn, _fd)) => {
-				conn.send(text).map_err(ServerError::Connection)
+				conn.send(data).map_err(ServerError::Connection)
 			}
 			None => Err(ServerError::InvalidIndex(id))
 		}
 	}
+
+	fn encoding(&self, id: ConnectionId) -> Option<Encoding> {
+		let (conn, _fd) = self.connections.get(&id)?;
+		Some(conn.encoding())
+	}
 	
 	#[cfg(any(target_os = "linux", target_os = "android"))]
 	fn get_name(&self, id: ConnectionId) -> Option<String> {
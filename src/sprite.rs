@@ -10,6 +10,7 @@ pub enum Sprite {
 	PlayerDefault,
 	Sage,
 	Dirt,
+	Tilled,
 	Grass1,
 	Grass2,
 	Grass3,
@@ -23,22 +24,33 @@ pub enum Sprite {
 	Bush,
 	Sanctuary,
 	Water,
+	Sand,
+	Snow,
+	Ice,
+	Channel,
 	Wall,
 	WoodWall,
 	Rock,
 	RockMid,
+	CaveEntrance,
+	CaveExit,
+	CopperOre,
 	Sapling,
 	YoungTree,
 	Tree,
 	OldTree,
 	OldTreeTinder,
+	PineTree,
+	Cactus,
 	Stone,
 	Pebble,
 	Crop,
 	Flower,
 	Reed,
 	Rush,
+	Weeds,
 	Lilypad,
+	Ripples,
 	Moss,
 	DeadLeaves,
 	PitcherPlant,
@@ -47,6 +59,7 @@ pub enum Sprite {
 	AshPlace,
 	MarkStone,
 	Altar,
+	Cache,
 	Seedling,
 	GreenStem,
 	BrownStem,
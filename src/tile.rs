@@ -7,8 +7,10 @@ use crate::{
 	action::{Action, InteractionType, CraftType, Interactable, InteractionResult},
 	tickstamp::Tickstamp,
 	worldmessages::SoundType,
+	pos::Layer,
 	hashmap,
 	crop::Crop,
+	basemap::Biome,
 };
 
 
@@ -19,14 +21,32 @@ use crate::{
 #[func(fn describe(&self) -> Option<&str>)]
 #[func(fn craft(&self) -> Option<CraftType>)]
 #[func(fn buildable(&self) -> bool {false})]
+#[func(fn fertile(&self) -> bool {false})]
 #[func(pub fn restoring(&self) -> bool {false})]
+#[func(fn interactions(&self) -> Vec<Interactable> {Vec::new()})]
+#[func(fn creeps_on(&self, ground: Ground) -> bool {false})]
+#[func(fn dig(&self) -> Option<Ground>)]
+#[func(pub fn is_channel(&self) -> bool {false})]
+#[func(pub fn water_source(&self) -> bool {false})]
+#[func(pub fn growth_percent(&self) -> i64 {100})]
 pub enum Ground {
 	#[assoc(sprite = Sprite::Dirt)]
 	#[assoc(describe = "Dirt")]
 	#[assoc(buildable = true)]
+	#[assoc(fertile = true)]
 	#[assoc(restoring = true)]
+	#[assoc(dig = Ground::Channel)]
+	#[assoc(clear = Ground::Tilled(Fertility::default()))]
 	Dirt,
 	
+	#[assoc(sprite = Sprite::Tilled)]
+	#[assoc(describe = _0.describe())]
+	#[assoc(buildable = true)]
+	#[assoc(fertile = true)]
+	#[assoc(interactions = _0.interactions())]
+	#[assoc(growth_percent = _0.growth_percent())]
+	Tilled(Fertility),
+	
 	#[assoc(clear = Ground::Dirt)]
 	#[assoc(sprite = Sprite::Grass1)]
 	#[assoc(describe = "Grass")]
@@ -44,12 +64,14 @@ pub enum Ground {
 	
 	#[assoc(clear = Ground::Dirt)]
 	#[assoc(sprite = Sprite::Moss)]
-	#[assoc(describe = "Moss")]
+	#[assoc(describe = "Moss. Slowly creeps over bare dirt")]
+	#[assoc(creeps_on = ground == Ground::Dirt)]
 	Moss,
 	
 	#[assoc(clear = Ground::Dirt)]
 	#[assoc(sprite = Sprite::DeadLeaves)]
-	#[assoc(describe = "Old leaves")]
+	#[assoc(describe = "Old leaves. Leaf mould can be gathered from below them")]
+	#[assoc(interactions = vec![Interactable::change_ground(InteractionType::Take, 0, Ground::Dirt, &[Item::Compost])])]
 	DeadLeaves,
 	
 	#[assoc(sprite = Sprite::Sanctuary)]
@@ -57,12 +79,36 @@ pub enum Ground {
 	Sanctuary,
 	
 	#[assoc(sprite = Sprite::Water)]
-	#[assoc(has_water = true)]
 	#[assoc(accessible = false)]
 	#[assoc(describe = "Water")]
 	#[assoc(craft = CraftType::Water)]
+	#[assoc(water_source = true)]
+	#[assoc(interactions = vec![Interactable::new(InteractionType::Fish, 1, &[0.4, 0.7], Some(Structure::Ripples), &[Item::Perch])])]
 	Water,
 	
+	#[assoc(sprite = Sprite::Water)]
+	#[assoc(accessible = false)]
+	#[assoc(describe = "Bog water")]
+	#[assoc(craft = CraftType::Water)]
+	#[assoc(water_source = true)]
+	#[assoc(interactions = vec![Interactable::new(InteractionType::Fish, 1, &[0.3, 0.6], Some(Structure::Ripples), &[Item::Eel])])]
+	BogWater,
+	
+	#[assoc(sprite = Sprite::Channel)]
+	#[assoc(accessible = false)]
+	#[assoc(describe = "Dry channel. Will carry water when connected to a lake")]
+	#[assoc(dig = Ground::Dirt)]
+	#[assoc(is_channel = true)]
+	Channel,
+	
+	#[assoc(sprite = Sprite::Water)]
+	#[assoc(accessible = false)]
+	#[assoc(describe = "Channel with shallow water. Crops next to it don't need watering")]
+	#[assoc(craft = CraftType::Water)]
+	#[assoc(dig = Ground::Dirt)]
+	#[assoc(is_channel = true)]
+	WetChannel,
+	
 	#[assoc(sprite = Sprite::RockFloor)]
 	#[assoc(buildable = true)]
 	#[assoc(describe = "Rock floor")]
@@ -77,6 +123,18 @@ pub enum Ground {
 	#[assoc(describe = "Wooden plank floor")]
 	WoodFloor,
 	
+	#[assoc(sprite = Sprite::Sand)]
+	#[assoc(describe = "Sand")]
+	Sand,
+	
+	#[assoc(sprite = Sprite::Snow)]
+	#[assoc(describe = "Snow")]
+	Snow,
+	
+	#[assoc(sprite = Sprite::Ice)]
+	#[assoc(describe = "Ice. A frozen lake")]
+	Ice,
+	
 	#[assoc(accessible = false)]
 	Empty
 }
@@ -94,6 +152,13 @@ pub enum Ground {
 #[func(fn craft(&self) -> Option<CraftType>)]
 #[func(fn grow(&self) -> Option<(i64, Structure, Option<Structure>)>)]
 #[func(fn join(&self, other: Structure) -> Option<Structure>)]
+#[func(fn spreads_on(&self, ground: Ground) -> bool {false})]
+#[func(pub fn habitat(&self) -> Option<Habitat>)]
+#[func(pub fn irrigated(&self) -> Option<Structure>)]
+#[func(fn harvestable(&self) -> bool {false})]
+#[func(pub fn portal(&self) -> Option<Layer>)]
+#[func(pub fn timer(&self) -> Option<(i64, Structure)>)]
+#[func(fn fish_odds(&self) -> Option<f32>)]
 pub enum Structure {
 	#[assoc(is_open = true)]
 	Air,
@@ -110,42 +175,81 @@ pub enum Structure {
 	
 	#[assoc(sprite = Sprite::Rock)]
 	#[assoc(blocking = true)]
-	#[assoc(describe = "Natural rock wall")]
+	#[assoc(describe = "Natural rock wall. Can be mined with a strong stone tool")]
+	#[assoc(interactions = vec![Interactable::harvest(InteractionType::Mine, 2, &[0.5, 1.0], &[Item::Stone, Item::Pebble])])]
 	Rock,
 	
 	#[assoc(sprite = Sprite::RockMid)]
 	#[assoc(blocking = true)]
-	#[assoc(describe = "Natural rock wall")]
+	#[assoc(describe = "Natural rock wall. Can only be mined with a very strong stone tool")]
+	#[assoc(interactions = vec![Interactable::harvest(InteractionType::Mine, 3, &[0.5, 1.0], &[Item::Stone, Item::Pebble])])]
 	RockMid,
 	
 	#[assoc(sprite = Sprite::Sapling)]
-	#[assoc(describe = "Sapling")]
+	#[assoc(describe = "Sapling. Can be dug up and planted elsewhere")]
+	#[assoc(interactions = vec![Interactable::take(&[Item::Sapling])])]
+	#[assoc(grow = (2, Structure::YoungTree, None))]
 	Sapling,
 	
 	#[assoc(sprite = Sprite::YoungTree)]
 	#[assoc(blocking = true)]
 	#[assoc(describe = "Young tree")]
 	#[assoc(interactions = vec![Interactable::harvest(InteractionType::Chop, 1, &[1.0], &[Item::Stick])])]
+	#[assoc(grow = (4, Structure::Tree, None))]
 	YoungTree,
 	
 	#[assoc(sprite = Sprite::Tree)]
 	#[assoc(blocking = true)]
-	#[assoc(describe = "Tree")]
-	#[assoc(interactions = vec![Interactable::harvest(InteractionType::Chop, 1, &[1.0], &[Item::Log])])]
+	#[assoc(describe = "Tree. Saplings can be cut from its roots")]
+	#[assoc(interactions = vec![
+		Interactable::harvest(InteractionType::Chop, 1, &[1.0], &[Item::Log]),
+		Interactable::new(InteractionType::Cut, 1, &[0.3, 0.6], None, &[Item::Sapling])
+	])]
+	#[assoc(grow = (24, Structure::OldTree, None))]
 	Tree,
 	
 	#[assoc(sprite = Sprite::OldTree)]
 	#[assoc(blocking = true)]
 	#[assoc(describe = "Dead tree")]
 	#[assoc(interactions = vec![Interactable::harvest(InteractionType::Chop, 1, &[1.0], &[Item::Stick])])]
+	#[assoc(grow = (4, Structure::OldTreeTinder, None))]
 	OldTree,
 	
 	#[assoc(sprite = Sprite::OldTreeTinder)]
 	#[assoc(blocking = true)]
 	#[assoc(interactions = vec![Interactable::new(InteractionType::Cut, 1, &[0.5, 1.0], Some(Structure::OldTree), &[Item::Tinder])])]
 	#[assoc(describe = "Dead tree with tinder fungus on it")]
+	#[assoc(grow = (8, Structure::Air, None))]
 	OldTreeTinder,
 	
+	#[assoc(sprite = Sprite::CaveEntrance)]
+	#[assoc(describe = "Cave entrance. A dark passage leads down into the rock")]
+	#[assoc(portal = Layer::UNDERGROUND)]
+	CaveEntrance,
+	
+	#[assoc(sprite = Sprite::CaveExit)]
+	#[assoc(describe = "Cave exit. Daylight shines down from the surface")]
+	#[assoc(portal = Layer::SURFACE)]
+	CaveExit,
+	
+	#[assoc(sprite = Sprite::CopperOre)]
+	#[assoc(blocking = true)]
+	#[assoc(describe = "Copper ore in the rock. Can be mined with a strong stone tool")]
+	#[assoc(interactions = vec![Interactable::harvest(InteractionType::Mine, 2, &[0.5, 1.0], &[Item::CopperOre])])]
+	CopperOre,
+	
+	#[assoc(sprite = Sprite::PineTree)]
+	#[assoc(blocking = true)]
+	#[assoc(describe = "Pine tree")]
+	#[assoc(interactions = vec![Interactable::harvest(InteractionType::Chop, 1, &[1.0], &[Item::Log])])]
+	PineTree,
+	
+	#[assoc(sprite = Sprite::Cactus)]
+	#[assoc(blocking = true)]
+	#[assoc(describe = "Cactus. It might carry fruit that can be cut off")]
+	#[assoc(interactions = vec![Interactable::harvest(InteractionType::Cut, 1, &[0.5, 1.0], &[Item::CactusFruit])])]
+	Cactus,
+	
 	#[assoc(sprite = Sprite::DenseGrass)]
 	#[assoc(interactions = vec![Interactable::harvest(InteractionType::Take, 0, &[0.1], &[Item::GreenSeed])])]
 	#[assoc(describe = "Dense grass")]
@@ -179,9 +283,24 @@ pub enum Structure {
 	
 	#[assoc(sprite = Sprite::Reed)]
 	#[assoc(interactions = vec![Interactable::harvest(InteractionType::Cut, 1, &[0.5, 1.0], &[Item::Reed])])]
-	#[assoc(describe = "Reeds. Can be cut")]
+	#[assoc(describe = "Reeds. Can be cut. They spread over the muddy shore")]
+	#[assoc(spreads_on = ground == Ground::Dirt)]
+	#[assoc(habitat = Habitat::Shore)]
 	Reed,
 	
+	#[assoc(sprite = Sprite::Weeds)]
+	#[assoc(describe = "Weeds. They take over untended soil and can be removed with a hoe")]
+	#[assoc(interactions = vec![Interactable::transform(InteractionType::Weed, 1, Structure::Air)])]
+	#[assoc(spreads_on = ground == Ground::Dirt)]
+	#[assoc(habitat = Habitat::Claims)]
+	Weeds,
+	
+	#[assoc(sprite = Sprite::Ripples)]
+	#[assoc(describe = "Rippling water. The fish are wary here for a while")]
+	#[assoc(timer = (1200, Structure::Air))]
+	#[assoc(fish_odds = 0.25)]
+	Ripples,
+	
 	#[assoc(sprite = Sprite::PitcherPlant)]
 	#[assoc(interactions = vec![Interactable::harvest(InteractionType::Cut, 1, &[0.5, 1.0], &[Item::Pitcher])])]
 	#[assoc(describe = "Pitcher plant. Can be cut")]
@@ -224,15 +343,42 @@ pub enum Structure {
 	#[assoc(sprite = Sprite::Fireplace)]
 	#[assoc(blocking = true)]
 	#[assoc(describe = "Fireplace. Safe place to have a fire")]
-	#[assoc(interactions = vec![Interactable::transform(InteractionType::Fuel, 1, Structure::Fire)])]
+	#[assoc(interactions = vec![
+		Interactable::transform(InteractionType::Fuel, 2, Structure::StokedFire),
+		Interactable::transform(InteractionType::Fuel, 1, Structure::Fire)
+	])]
 	Fireplace,
 	
 	#[assoc(sprite = Sprite::Fire)]
 	#[assoc(blocking = true)]
-	#[assoc(describe = "Fire. Safely contained in fireplace.")]
-	#[assoc(grow = (1, Structure::AshPlace, None))]
+	#[assoc(describe = "Fire. Safely contained in fireplace. It will go out soon unless more fuel is added. Food can be cooked on it")]
+	#[assoc(craft = CraftType::Cooking)]
+	#[assoc(interactions = vec![
+		Interactable::transform(InteractionType::Fuel, 2, Structure::BlazingFire),
+		Interactable::transform(InteractionType::Fuel, 1, Structure::StokedFire)
+	])]
+	#[assoc(timer = (3000, Structure::AshPlace))]
 	Fire,
 	
+	#[assoc(sprite = Sprite::Fire)]
+	#[assoc(blocking = true)]
+	#[assoc(describe = "Fire. Burning steadily in the fireplace. Food can be cooked on it")]
+	#[assoc(craft = CraftType::Cooking)]
+	#[assoc(interactions = vec![
+		Interactable::transform(InteractionType::Fuel, 2, Structure::BlazingFire),
+		Interactable::transform(InteractionType::Fuel, 1, Structure::StokedFire)
+	])]
+	#[assoc(timer = (3000, Structure::Fire))]
+	StokedFire,
+	
+	#[assoc(sprite = Sprite::Fire)]
+	#[assoc(blocking = true)]
+	#[assoc(describe = "Fire. Blazing in the fireplace. It will burn for a long time. Food can be cooked on it")]
+	#[assoc(craft = CraftType::Cooking)]
+	#[assoc(interactions = vec![Interactable::transform(InteractionType::Fuel, 1, Structure::BlazingFire)])]
+	#[assoc(timer = (6000, Structure::StokedFire))]
+	BlazingFire,
+	
 	#[assoc(sprite = Sprite::AshPlace)]
 	#[assoc(blocking = true)]
 	#[assoc(describe = "Fireplace. Filled with ash")]
@@ -251,6 +397,12 @@ pub enum Structure {
 	#[assoc(craft = CraftType::Marker)]
 	MarkerAltar,
 	
+	#[assoc(sprite = Sprite::Cache)]
+	#[assoc(blocking = true)]
+	#[assoc(describe = "An old cache. Someone left supplies here long ago")]
+	#[assoc(interactions = vec![Interactable::take(&[Item::Tinder, Item::SharpStone, Item::Reed, Item::YellowSeed, Item::BrownSeed])])]
+	Cache,
+	
 	#[assoc(sprite = Sprite::MarkStone)]
 	#[assoc(blocking = true)]
 	#[assoc(describe = "Mark stone. Center of a land claim")]
@@ -264,36 +416,43 @@ pub enum Structure {
 	#[assoc(sprite = Sprite::SeedingHardwood)]
 	#[assoc(describe = "Seeding Hardwood")]
 	#[assoc(interactions = vec![Interactable::take(&[Item::BrownSeed])])]
+	#[assoc(harvestable = true)]
 	SeedingHardwood,
 	
 	#[assoc(sprite = Sprite::SeedingDiscLeaf)]
 	#[assoc(describe = "Seeding Disc plant")]
 	#[assoc(interactions = vec![Interactable::take(&[Item::GreenSeed])])]
+	#[assoc(harvestable = true)]
 	SeedingDiscLeaf,
 	
 	#[assoc(sprite = Sprite::SeedingKnifeLeaf)]
 	#[assoc(describe = "Seeding Knife plant")]
 	#[assoc(interactions = vec![Interactable::take(&[Item::YellowSeed])])]
+	#[assoc(harvestable = true)]
 	SeedingKnifeLeaf,
 	
 	#[assoc(sprite = Sprite::DiscLeaf)]
 	#[assoc(describe = "DiscLeaf")]
 	#[assoc(interactions = vec![Interactable::take(&[Item::DiscLeaf])])]
+	#[assoc(harvestable = true)]
 	DiscLeaf,
 	
 	#[assoc(sprite = Sprite::KnifeLeaf)]
 	#[assoc(describe = "KnifeLeaf")]
 	#[assoc(interactions = vec![Interactable::take(&[Item::KnifeLeaf])])]
+	#[assoc(harvestable = true)]
 	KnifeLeaf,
 	
 	#[assoc(sprite = Sprite::HardwoodStick)]
 	#[assoc(describe = "Hardwood stick")]
 	#[assoc(interactions = vec![Interactable::take(&[Item::HardwoodStick])])]
+	#[assoc(harvestable = true)]
 	HardwoodStick,
 	
 	#[assoc(sprite = Sprite::HardwoodKnife)]
 	#[assoc(describe = "Hardwood knife")]
 	#[assoc(interactions = vec![Interactable::take(&[Item::HardwoodKnife])])]
+	#[assoc(harvestable = true)]
 	HardwoodKnife,
 	
 	#[assoc(sprite = Sprite::HardwoodTable)]
@@ -313,6 +472,7 @@ pub enum Structure {
 	#[assoc(sprite = Sprite::SawBlade)]
 	#[assoc(describe = "Saw blade")]
 	#[assoc(interactions = vec![Interactable::take(&[Item::SawBlade])])]
+	#[assoc(harvestable = true)]
 	SawBlade,
 	
 	#[assoc(sprite = Sprite::WoodWall)]
@@ -326,10 +486,70 @@ pub enum Structure {
 	#[assoc(interactions = _0.all_interactions())]
 	#[assoc(grow = _0.grow()?)]
 	#[assoc(join = _0.join(other)?)]
+	#[assoc(irrigated = _0.irrigated()?)]
 	Crop(Crop),
 }
 
 
+/// Where a wild plant can spread to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Habitat {
+	/// Next to lake or bog water
+	Shore,
+	/// Near a land claim
+	Claims,
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fertility(u8);
+
+impl Fertility {
+	const MAX: u8 = 4;
+	const RICH: u8 = 3;
+	
+	fn describe(&self) -> &'static str {
+		match self.0 {
+			0 => "Tilled soil. It is exhausted and crops will grow very slowly",
+			1 => "Tilled soil. It is poor and crops will grow slowly",
+			2 => "Tilled soil",
+			_ => "Tilled soil. It is rich and crops will grow quickly and yield more",
+		}
+	}
+	
+	fn interactions(&self) -> Vec<Interactable> {
+		if self.0 < Self::MAX {
+			vec![Interactable::change_ground(InteractionType::Fertilize, 1, Ground::Tilled(Self((self.0 + 2).min(Self::MAX))), &[])]
+		} else {
+			Vec::new()
+		}
+	}
+	
+	fn growth_percent(&self) -> i64 {
+		match self.0 {
+			0 => 200,
+			1 => 150,
+			2 => 100,
+			_ => 75,
+		}
+	}
+	
+	fn depleted(&self) -> Self {
+		Self(self.0.saturating_sub(1))
+	}
+	
+	fn is_rich(&self) -> bool {
+		self.0 >= Self::RICH
+	}
+}
+
+impl Default for Fertility {
+	fn default() -> Self {
+		Self(2)
+	}
+}
+
+
 impl Structure {
 	fn interactables(&self) -> Vec<Interactable> {
 		let mut interactions = self.interactions();
@@ -360,6 +580,20 @@ impl Tile {
 		Self{ground, structure}
 	}
 	
+	fn interactables(&self) -> Vec<Interactable> {
+		let mut interactions = self.structure.interactables();
+		if self.structure.is_open() {
+			interactions.append(&mut self.ground.interactions());
+		} else if let Some(odds) = self.structure.fish_odds() {
+			interactions.extend(
+				self.ground.interactions().into_iter()
+					.filter(|interactable| interactable.action_type() == InteractionType::Fish)
+					.map(|interactable| interactable.with_odds_factor(odds))
+			);
+		}
+		interactions
+	}
+	
 	pub fn sprites(&self) -> Vec<Sprite> {
 		[self.structure.sprite(), self.ground.sprite()].into_iter()
 			.flatten()
@@ -374,11 +608,12 @@ impl Tile {
 		self.structure.is_open() && self.ground.buildable()
 	}
 	
-	pub fn interact(&self, item: Item, time: Tickstamp) -> Option<InteractionResult> {
-		item.actions().into_iter().filter_map(|action| self.act(action, item, time)).next()
+	/// The biome is only used to describe the tile when it is inspected
+	pub fn interact(&self, item: Item, time: Tickstamp, biome: Option<Biome>) -> Option<InteractionResult> {
+		item.actions().into_iter().filter_map(|action| self.act(action, item, time, biome)).next()
 	}
 	
-	pub fn act(&self, action: Action, item: Item, time: Tickstamp) -> Option<InteractionResult> {
+	pub fn act(&self, action: Action, item: Item, time: Tickstamp, biome: Option<Biome>) -> Option<InteractionResult> {
 		if let Some(name) = self.structure.explain() {
 			if action != Action::Inspect {
 				return Some(InteractionResult {
@@ -389,10 +624,18 @@ impl Tile {
 		}
 		match action {
 			Action::Interact(interact) => {
-				let mut result = self.structure.interactables()
+				let mut result = self.interactables()
 					.into_iter()
 					.filter_map(|interactable| interactable.apply(interact, time))
 					.next()?;
+				if let Ground::Tilled(fertility) = self.ground {
+					if self.structure.harvestable() && result.remains.is_some_and(|remains| remains.is_open()) {
+						if fertility.is_rich() {
+							result.items.extend(result.items.clone());
+						}
+						result.remains_ground = Some(Ground::Tilled(fertility.depleted()));
+					}
+				}
 				if interact.use_item {
 					result.cost.insert(item, 1);
 				}
@@ -407,18 +650,20 @@ impl Tile {
 				} else {
 					None
 				}
-			Action::Inspect => 
+			Action::Inspect => {
+				let mut text = format!(
+					"{}  --  {}",
+					self.ground.describe().unwrap_or(""),
+					self.structure.description().unwrap_or_default()
+				);
+				if let Some(biome) = biome {
+					text = format!("{}  --  {}", text, biome.name());
+				}
 				Some(InteractionResult {
-					message: Some((
-						SoundType::Explain,
-						format!(
-							"{}  --  {}",
-							self.ground.describe().unwrap_or(""),
-							self.structure.description().unwrap_or_default()
-						)
-					)),
+					message: Some((SoundType::Explain, text)),
 					..Default::default()
-				}),
+				})
+			}
 			Action::BuildClaim(structure) =>
 				if self.can_build() {
 					Some(InteractionResult {
@@ -442,6 +687,25 @@ impl Tile {
 				} else {
 					None
 				}
+			Action::Plant(structure) =>
+				if self.structure.is_open() && self.ground.fertile() {
+					Some(InteractionResult {
+						remains: Some(structure),
+						cost: hashmap!{item => 1},
+						..Default::default()
+					})
+				} else {
+					None
+				}
+			Action::Dig =>
+				if self.structure.is_open() {
+					Some(InteractionResult {
+						remains_ground: Some(self.ground.dig()?),
+						..Default::default()
+					})
+				} else {
+					None
+				}
 			Action::Craft(typ, product, mut cost) => {
 				cost.entry(item).and_modify(|n| {*n += 1;}).or_insert(1);
 				if Some(typ) == self.structure.craft() || Some(typ) == self.ground.craft() {
@@ -460,6 +724,23 @@ impl Tile {
 	pub fn grow(&self) -> Option<(i64, Structure, Option<Structure>)> {
 		self.structure.grow()
 	}
+	
+	/// Plants that spread by themselves, and moss (which only creeps over dirt)
+	pub fn is_wild(&self) -> bool {
+		self.structure.habitat().is_some() || (self.structure.is_open() && self.ground.creeps_on(Ground::Dirt))
+	}
+	
+	pub fn spread_onto(&self, target: Tile) -> Option<Tile> {
+		if !target.structure.is_open() {
+			None
+		} else if self.structure.spreads_on(target.ground) {
+			Some(Tile::structure(target.ground, self.structure))
+		} else if self.structure.is_open() && self.ground.creeps_on(target.ground) {
+			Some(Tile::structure(self.ground, target.structure))
+		} else {
+			None
+		}
+	}
 }
 
 impl Default for Tile {
@@ -483,7 +764,95 @@ impl<'de> Deserialize<'de> for Tile {
 }
 
 
-
-
-
-
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	#[test]
+	fn fishing_makes_fish_wary() {
+		let water = Tile::ground(Ground::Water);
+		let result = water.interact(Item::FishingRod, Tickstamp(5), None).unwrap();
+		assert_eq!(result.remains, Some(Structure::Ripples));
+		let rippling = Tile::structure(Ground::Water, Structure::Ripples);
+		let catches = |tile: Tile| (0..400)
+			.filter(|t| !tile.interact(Item::FishingRod, Tickstamp(*t), None).unwrap().items.is_empty())
+			.count();
+		assert!(catches(rippling) > 0);
+		assert!(catches(rippling) * 2 < catches(water));
+		assert_eq!(rippling.interact(Item::FishingRod, Tickstamp(6), None).unwrap().remains, Some(Structure::Ripples));
+	}
+	
+	#[test]
+	fn stronger_fuel_makes_fire_burn_longer() {
+		let fire = Tile::structure(Ground::Dirt, Structure::Fire);
+		assert_eq!(fire.interact(Item::Stick, Tickstamp(1), None).unwrap().remains, Some(Structure::StokedFire));
+		assert_eq!(fire.interact(Item::Log, Tickstamp(1), None).unwrap().remains, Some(Structure::BlazingFire));
+	}
+	
+	#[test]
+	fn fish_can_be_cooked_on_fire() {
+		let fire = Tile::structure(Ground::Dirt, Structure::StokedFire);
+		let result = fire.interact(Item::Perch, Tickstamp(1), None).unwrap();
+		assert_eq!(result.items, vec![Item::GrilledPerch]);
+		assert_eq!(result.cost, hashmap!{Item::Perch => 1});
+		assert!(Tile::structure(Ground::Dirt, Structure::Fireplace).interact(Item::Perch, Tickstamp(1), None).is_none());
+	}
+	
+	#[test]
+	fn saplings_only_grow_in_dirt() {
+		let result = Tile::ground(Ground::Dirt).interact(Item::Sapling, Tickstamp(1), None).unwrap();
+		assert_eq!(result.remains, Some(Structure::Sapling));
+		assert_eq!(result.cost, hashmap!{Item::Sapling => 1});
+		assert!(!result.build);
+		assert!(Tile::ground(Ground::Grass1).interact(Item::Sapling, Tickstamp(1), None).is_none());
+		assert!(Tile::structure(Ground::Dirt, Structure::Sapling).interact(Item::Sapling, Tickstamp(1), None).is_none());
+	}
+	
+	#[test]
+	fn cutting_saplings_leaves_tree_growing() {
+		let tree = Tile::structure(Ground::Dirt, Structure::Tree);
+		let result = tree.interact(Item::SharpStone, Tickstamp(1), None).unwrap();
+		assert_eq!(result.remains, None);
+		assert_eq!(result.remains_ground, None);
+	}
+	
+	#[test]
+	fn hoe_removes_weeds() {
+		let weeds = Tile::structure(Ground::Dirt, Structure::Weeds);
+		assert_eq!(weeds.interact(Item::Hoe, Tickstamp(1), None).unwrap().remains, Some(Structure::Air));
+		let cleared = Tile::ground(Ground::Grass1).interact(Item::Hoe, Tickstamp(1), None).unwrap();
+		assert_eq!(cleared.remains_ground, Some(Ground::Dirt));
+	}
+	
+	#[test]
+	fn harvesting_exhausts_tilled_soil() {
+		let tilled = Tile::ground(Ground::Dirt).interact(Item::Hoe, Tickstamp(1), None).unwrap().remains_ground.unwrap();
+		assert_eq!(tilled, Ground::Tilled(Fertility(2)));
+		let result = Tile::structure(tilled, Structure::DiscLeaf).interact(Item::Hands, Tickstamp(1), None).unwrap();
+		assert_eq!(result.items, vec![Item::DiscLeaf]);
+		assert_eq!(result.remains_ground, Some(Ground::Tilled(Fertility(1))));
+		let fertilized = Tile::ground(Ground::Tilled(Fertility(1))).interact(Item::Ash, Tickstamp(1), None).unwrap();
+		assert_eq!(fertilized.remains_ground, Some(Ground::Tilled(Fertility(3))));
+		assert_eq!(fertilized.cost, hashmap!{Item::Ash => 1});
+		let rich = Tile::structure(Ground::Tilled(Fertility(3)), Structure::DiscLeaf).interact(Item::Hands, Tickstamp(1), None).unwrap();
+		assert_eq!(rich.items, vec![Item::DiscLeaf, Item::DiscLeaf]);
+	}
+	
+	#[test]
+	fn catch_depends_on_water() {
+		let catch = |ground| (0..20)
+			.flat_map(|t| Tile::ground(ground).interact(Item::FishingRod, Tickstamp(t), None).unwrap().items)
+			.collect::<Vec<Item>>();
+		assert!(catch(Ground::Water).into_iter().all(|item| item == Item::Perch));
+		assert!(catch(Ground::BogWater).into_iter().all(|item| item == Item::Eel));
+		assert!(!catch(Ground::BogWater).is_empty());
+	}
+	
+	#[test]
+	fn inspecting_names_the_biome() {
+		let tile = Tile::structure(Ground::Dirt, Structure::Tree);
+		let text = |biome| tile.interact(Item::Eyes, Tickstamp(1), biome).unwrap().message.unwrap().1;
+		assert!(text(Some(Biome::Forest)).ends_with("  --  forest"));
+		assert!(!text(None).contains("forest"));
+	}
+}
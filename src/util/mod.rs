@@ -1,6 +1,7 @@
 
 mod holder;
 pub mod math;
+pub mod parallel;
 
 pub use holder::{Holder, HolderId};
 
@@ -43,6 +44,11 @@ macro_rules! hashmap {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Percentage(pub i64);
 
+/// FNV-1a hash. Unlike DefaultHasher it gives the same result in every build
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+	bytes.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
+
 
 #[cfg(test)]
 mod tests {
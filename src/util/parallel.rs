@@ -0,0 +1,76 @@
+
+use std::thread;
+use std::num::NonZeroUsize;
+use crate::pos::{Pos, Area};
+
+/// Areas smaller than this are not worth starting threads for
+const MIN_PARALLEL_SURFACE: i32 = 4096;
+
+/// Run f on horizontal bands of the area in parallel.
+/// The results are concatenated in band order, so when f gives its results in row order the output is the same as f(area)
+pub fn in_bands<T: Send>(area: Area, f: impl Fn(Area) -> Vec<T> + Sync) -> Vec<T> {
+	let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+	in_n_bands(threads, area, f)
+}
+
+fn in_n_bands<T: Send>(threads: usize, area: Area, f: impl Fn(Area) -> Vec<T> + Sync) -> Vec<T> {
+	let threads = threads.min(area.size().y.max(1) as usize) as i32;
+	if threads <= 1 || area.surface() < MIN_PARALLEL_SURFACE {
+		return f(area);
+	}
+	let band_height = (area.size().y + threads - 1) / threads;
+	let f = &f;
+	thread::scope(|scope| {
+		area.blocks(Pos::new(area.size().x, band_height))
+			.map(|band| scope.spawn(move || f(band)))
+			.collect::<Vec<_>>()
+			.into_iter()
+			.flat_map(|handle| handle.join().unwrap())
+			.collect()
+	})
+}
+
+/// Run f on all items in parallel. The results are in the order of the items
+pub fn map<T: Send, R: Send>(items: Vec<T>, f: impl Fn(T) -> R + Sync) -> Vec<R> {
+	let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+	map_n(threads, items, f)
+}
+
+fn map_n<T: Send, R: Send>(threads: usize, items: Vec<T>, f: impl Fn(T) -> R + Sync) -> Vec<R> {
+	if threads <= 1 || items.len() <= 1 {
+		return items.into_iter().map(f).collect();
+	}
+	let per_thread = items.len().div_ceil(threads);
+	let mut items = items.into_iter();
+	let f = &f;
+	thread::scope(|scope| {
+		std::iter::from_fn(|| Some(items.by_ref().take(per_thread).collect::<Vec<T>>()).filter(|part| !part.is_empty()))
+			.map(|part| scope.spawn(move || part.into_iter().map(f).collect::<Vec<R>>()))
+			.collect::<Vec<_>>()
+			.into_iter()
+			.flat_map(|handle| handle.join().unwrap())
+			.collect()
+	})
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn bands_keep_area_order() {
+		let area = Area::new(Pos::new(-30, 17), Pos::new(100, 77));
+		for threads in [1, 3, 8] {
+			let positions = in_n_bands(threads, area, |band| band.iter().collect());
+			assert_eq!(positions, area.iter().collect::<Vec<Pos>>());
+		}
+	}
+
+	#[test]
+	fn map_keeps_item_order() {
+		for threads in [1, 3, 8] {
+			assert_eq!(map_n(threads, (0..20).collect(), |n| n * 2), (0..20).map(|n| n * 2).collect::<Vec<i32>>());
+		}
+	}
+}
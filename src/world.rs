@@ -1,20 +1,26 @@
 
 use std::collections::{HashMap};
+use std::sync::Arc;
 use serde::{Serialize, Deserialize};
 
 use crate::{
 	PlayerId,
 	controls::{Control},
-	pos::{Pos, Area},
+	pos::{Pos, Area, Layer},
 	vec2::Vec2,
-	util::Holder,
+	util::{Holder, parallel},
 	sprite::Sprite,
-	worldmessages::{WorldMessage, SectionMessage, ViewAreaMessage, ChangeMessage, SoundType::{BuildError}},
+	tile::Tile,
+	worldmessages::{WorldMessage, SectionMessage, ViewAreaMessage, ChangeMessage, BiomeMessage, DynamicMessage, SoundType::{BuildError}, CHUNK_SIZE},
 	tickstamp::{Tickstamp},
 	timestamp::Timestamp,
 	creature::{Creature, Mind, CreatureId, PlayerSave, CreatureView},
-	player::Player,
-	map::{Map, MapSave}
+	player::{Player, Compass},
+	map::{Map, MapSave, Timer},
+	basemap::{WorldType, Generator},
+	worldgen::WorldGenSettings,
+	chunk::ChunkStore,
+	errors::AnyError,
 };
 
 const EDGE_OFFSET: i32 = 32;
@@ -23,30 +29,65 @@ const VIEW_AREA_SIZE: Pos = Pos::new(128, 128);
 pub struct World {
 	pub name: String,
 	pub time: Tickstamp,
-	ground: Map,
+	ground: Layers,
 	players: HashMap<PlayerId, Player>,
 	creatures: Holder<CreatureId, Creature>,
 	claims: HashMap<PlayerId, Pos>,
-	seed: u32
 }
 
 impl World {
 	
-	pub fn new(name: String, seed: u32) -> Self {
+	pub fn new(name: String, seed: u32, world_type: WorldType, worldgen: WorldGenSettings) -> Self {
 		let time = Tickstamp(0);
 		Self {
 			name,
-			ground: Map::new(seed, time),
+			ground: Layers {
+				maps: HashMap::new(),
+				world_type,
+				worldgen,
+				generator: Generator::LATEST,
+				seed,
+				store: None
+			},
 			players: HashMap::new(),
 			creatures: Holder::new(),
 			time,
 			claims: HashMap::new(),
-			seed
 		}
 	}
 	
+	/// Keep the map chunks in store from now on
+	pub fn set_chunk_store(&mut self, store: Arc<dyn ChunkStore>) {
+		for (layer, map) in self.ground.maps.iter_mut() {
+			map.set_store(*layer, store.clone());
+		}
+		self.ground.store = Some(store);
+	}
+	
+	pub fn save_chunks(&mut self) -> Result<(), AnyError> {
+		let mut result = Ok(());
+		for map in self.ground.maps.values_mut() {
+			if let Err(err) = map.save_chunks() {
+				result = Err(err);
+			}
+		}
+		result
+	}
+	
+	fn surface(&mut self) -> &mut Map {
+		self.ground.get(Layer::SURFACE, self.time)
+	}
+	
+	pub fn map(&mut self, layer: Layer) -> &mut Map {
+		self.ground.get(layer, self.time)
+	}
+	
+	pub fn claims(&self) -> Vec<Pos> {
+		self.claims.values().copied().collect()
+	}
+	
 	pub fn default_player(&mut self) -> PlayerSave {
-		PlayerSave::new(Vec2::from_pos(&self.ground.player_spawn()))
+		PlayerSave::new(Vec2::from_pos(&self.surface().player_spawn()))
 	}
 	
 	pub fn add_player(&mut self, playerid: &PlayerId, saved: PlayerSave) -> Result<(), PlayerError> {
@@ -82,6 +123,13 @@ impl World {
 		Ok(())
 	}
 	
+	/// Send the whole view area of the player again in the next view
+	pub fn redraw_player(&mut self, playerid: &PlayerId) -> Result<(), PlayerError> {
+		let player = self.players.get_mut(playerid).ok_or_else(|| PlayerError::NotFound(playerid.clone()))?;
+		player.view_area = None;
+		Ok(())
+	}
+	
 	pub fn has_player(&mut self, playerid: &PlayerId) -> bool {
 		self.players.contains_key(playerid)
 	}
@@ -115,15 +163,18 @@ impl World {
 			let movement: Option<Vec2> = match &creature.mind {
 				Mind::Player(playerid) => self.players.get(playerid).and_then(|player| player.movement)
 			};
+			let ground = self.ground.get(creature.layer, self.time);
 			if let Some(delta) = movement {
+				let oldpos = creature.pos.round();
 				let newpos = creature.pos + delta * creature.speed();
 				let blocking = creature.shape
 					.moved(newpos)
 					.outer_area()
 					.iter()
-					.any(|p| self.ground.cell(p).blocking());
+					.any(|p| ground.cell(p).blocking());
 				if !blocking {
 					creature.pos = newpos;
+					enter_portal(ground, creature, oldpos);
 				}
 			}
 
@@ -134,8 +185,10 @@ impl World {
 			match plan {
 				Control::Move(direction) => {
 					creature.cooldown = creature.walk_cooldown;
+					let oldpos = creature.pos.round();
 					let newpos = creature.pos + Vec2::from_pos(&(Pos::new(0, 0) + *direction)) / 2.0;
 					creature.pos = newpos;
+					enter_portal(ground, creature, oldpos);
 					// let tile = self.ground.cell(newpos);
 					// if !tile.blocking() {
 						// creature.pos = newpos;
@@ -151,70 +204,79 @@ impl World {
 				Control::MoveSelected(selector) => {
 					creature.inventory.move_selected(*selector);
 				}
-				Control::Interact(direction) => { }
-				// 	let pos = creature.pos + direction.map(|dir| dir.to_position()).unwrap_or_else(Pos::zero);
-				// 	let tile = self.ground.cell(pos);
-				// 	let item = creature.inventory.selected();
-				// 	let Some(interaction) = tile.interact(item, self.time)
-				// 		else {
-				// 			continue
-				// 		};
-				// 	if interaction.claim {
-				// 		if let Some(player_id) = creature.player() {
-				// 			if self.claims.contains_key(&player_id) {
-				// 				creature.heard_sounds.push((BuildError, "Only one claim per player allowed".to_string()));
-				// 				continue;
-				// 			}
-				// 			if self.claims.values().any(|p| p.distance_to(pos) < 64) {
-				// 				creature.heard_sounds.push((BuildError, "Too close to existing claim".to_string()));
-				// 				continue;
-				// 			}
-				// 			if pos.distance_to(self.ground.player_spawn()) < 96 {
-				// 				creature.heard_sounds.push((BuildError, "Too close to spawn".to_string()));
-				// 				continue;
-				// 			}
-				// 			self.claims.insert(player_id, pos);
-				// 		} else {
-				// 			creature.heard_sounds.push((
-				// 				BuildError,
-				// 				"Only players can claim land and you're not a player. If you read this something has probably gone wrong.".to_string()
-				// 			));
-				// 			continue;
-				// 		}
-				// 	}
-				// 	if interaction.build {
-				// 		if let Some(claim_pos) = creature.player().as_ref().and_then(|player_id| self.claims.get(player_id)) {
-				// 			if pos.distance_to(*claim_pos) > 24 {
-				// 				creature.heard_sounds.push((
-				// 					BuildError,
-				// 					"Too far from land claim to build".to_string()
-				// 				));
-				// 				continue;
-				// 			}
-				// 		} else {
-				// 			creature.heard_sounds.push((
-				// 				BuildError,
-				// 				"Need land claim to build".to_string()
-				// 			));
-				// 			continue;
-				// 		}
-				// 	}
-				// 	if !creature.inventory.pay(interaction.cost) {
-				// 		continue;
-				// 	}
-				// 	for item in interaction.items {
-				// 		creature.inventory.add(item);
-				// 	}
-				// 	if let Some(remains) = interaction.remains {
-				// 		self.ground.set_structure(pos, remains);
-				// 	}
-				// 	if let Some(remains_ground) = interaction.remains_ground {
-				// 		self.ground.set_ground(pos, remains_ground);
-				// 	}
-				// 	if let Some(message) = interaction.message {
-				// 		creature.heard_sounds.push(message);
-				// 	}
-				// }
+				Control::Interact(direction) => {
+					let pos = creature.pos.round() + direction.map(|dir| dir.to_position()).unwrap_or_else(Pos::zero);
+					let tile = ground.cell(pos);
+					let item = creature.inventory.selected();
+					let biome = ground.meta(pos).map(|meta| meta.biome);
+					let Some(interaction) = tile.interact(item, self.time, biome)
+						else {
+							continue
+						};
+					if interaction.claim {
+						if creature.layer != Layer::SURFACE {
+							creature.heard_sounds.push((BuildError, "Land can only be claimed on the surface".to_string()));
+							continue;
+						}
+						if let Some(player_id) = creature.player() {
+							if self.claims.contains_key(&player_id) {
+								creature.heard_sounds.push((BuildError, "Only one claim per player allowed".to_string()));
+								continue;
+							}
+							if self.claims.values().any(|p| p.distance_to(pos) < 64) {
+								creature.heard_sounds.push((BuildError, "Too close to existing claim".to_string()));
+								continue;
+							}
+							if pos.distance_to(ground.player_spawn()) < 96 {
+								creature.heard_sounds.push((BuildError, "Too close to spawn".to_string()));
+								continue;
+							}
+							self.claims.insert(player_id, pos);
+						} else {
+							creature.heard_sounds.push((
+								BuildError,
+								"Only players can claim land and you're not a player. If you read this something has probably gone wrong.".to_string()
+							));
+							continue;
+						}
+					}
+					if interaction.build {
+						if let Some(claim_pos) = creature.player().as_ref().and_then(|player_id| self.claims.get(player_id)) {
+							if creature.layer != Layer::SURFACE || pos.distance_to(*claim_pos) > 24 {
+								creature.heard_sounds.push((
+									BuildError,
+									"Too far from land claim to build".to_string()
+								));
+								continue;
+							}
+						} else {
+							creature.heard_sounds.push((
+								BuildError,
+								"Need land claim to build".to_string()
+							));
+							continue;
+						}
+					}
+					if (interaction.remains.is_some() || interaction.remains_ground.is_some()) && ground.changeable(pos).is_err() {
+						creature.heard_sounds.push((BuildError, "This place can't be changed right now".to_string()));
+						continue;
+					}
+					if !creature.inventory.pay(interaction.cost) {
+						continue;
+					}
+					for item in interaction.items {
+						creature.inventory.add(item);
+					}
+					if let Some(remains) = interaction.remains {
+						ground.set_structure(pos, remains);
+					}
+					if let Some(remains_ground) = interaction.remains_ground {
+						ground.set_ground(pos, remains_ground);
+					}
+					if let Some(message) = interaction.message {
+						creature.heard_sounds.push(message);
+					}
+				}
 			}
 		}
 		for player in self.players.values_mut() {
@@ -223,8 +285,9 @@ impl World {
 		Some(())
 	}
 	
-	fn loaded_areas(&self) -> Vec<Area> {
+	fn loaded_areas(&self, layer: Layer) -> Vec<Area> {
 		self.players.values()
+			.filter(|player| player.view_layer == layer)
 			.filter_map(Player::view_area)
 			.collect()
 	}
@@ -232,7 +295,16 @@ impl World {
 	pub fn update(&mut self) {
 		self.update_creatures();
 		
-		self.ground.tick(self.time, self.loaded_areas());
+		let layers: Vec<Layer> = self.ground.maps.keys().copied().collect();
+		for layer in layers {
+			let areas = self.loaded_areas(layer);
+			let claims = if layer == Layer::SURFACE {
+				self.claims.values().copied().collect()
+			} else {
+				Vec::new()
+			};
+			self.ground.get(layer, self.time).tick(self.time, areas, claims);
+		}
 		
 		self.time.increment();
 	}
@@ -246,12 +318,15 @@ impl World {
 	// 	}).collect()
 	// }
 	
-	fn draw_changes(&mut self) -> Option<ChangeMessage> {
-		Some(
-			self.ground.modified().into_iter()
-				.map(|(pos, tile)| (pos, tile.sprites()))
-				.collect()
-		)
+	fn draw_changes(&mut self) -> HashMap<Layer, ChangeMessage> {
+		self.ground.maps.iter_mut()
+			.map(|(layer, map)| (
+				*layer,
+				map.modified().into_iter()
+					.map(|(pos, tile)| (pos, tile.sprites()))
+					.collect()
+			))
+			.collect()
 	}
 		// if let Some(last_drawing) = &self.drawing {
 		// 	for pos in last_drawing.keys() {
@@ -271,13 +346,20 @@ impl World {
 	pub fn view(&mut self) -> HashMap<PlayerId, WorldMessage> {
 		let changes = self.draw_changes();
 		let mut views: HashMap<PlayerId, WorldMessage> = HashMap::new();
-		let dynamics: Vec<CreatureView> = self.players.values()
-			.filter_map(|player| self.creatures.get(&player.body))
-			.map(|creature| creature.view())
+		let dynamics: Vec<(Layer, CreatureView)> = self.players.values()
+			.filter_map(|player| Some((player.body, self.creatures.get(&player.body)?)))
+			.map(|(id, creature)| (creature.layer, creature.view(id)))
 			.collect();
+		// the base tiles and the sections are generated for all players at once, only adding the changes needs the map mutably
+		let mut redraws: Vec<(PlayerId, Layer, Area)> = Vec::new();
 		for (playerid, player) in self.players.iter_mut() {
 			let mut wm = WorldMessage::default();
 			if let Some(body) = self.creatures.get(&player.body) {
+				if player.view_layer != body.layer {
+					player.view_layer = body.layer;
+					player.view_area = None;
+				}
+				let ground = self.ground.get(body.layer, self.time);
 				let in_view_range = player.view_area()
 					.map_or(
 						false,
@@ -291,13 +373,41 @@ impl World {
 					let (total_area, redraw_area) = Self::new_view_area(body.pos.round(), &player.view_area);
 					player.view_area = Some(total_area);
 					wm.viewarea = Some(ViewAreaMessage{area: total_area});
-					wm.section = Some(draw_field(redraw_area, &mut self.ground));
-				}
-				if changes.is_some() {
-					wm.change = changes.clone();
+					ground.prepare(redraw_area);
+					redraws.push((playerid.clone(), body.layer, redraw_area));
 				}
+				// only send what happens within the view area
+				let view_area = player.view_area().unwrap_or_default();
+				wm.change = changes.get(&body.layer)
+					.map(|change|
+						change.iter()
+							.filter(|(pos, _)| view_area.contains(*pos))
+							.cloned()
+							.collect::<ChangeMessage>()
+					)
+					.filter(|change| !change.is_empty());
 				wm.pos = Some(body.pos);
-				wm.dynamics = Some(dynamics.clone());
+				wm.layer = Some(body.layer);
+				if let Some(meta) = ground.meta(body.pos.round()) {
+					// the nearest biomes only change noticeably when the player enters another biome cell
+					if player.compass.as_ref().map(|compass| (compass.layer, compass.core)) != Some((body.layer, meta.core)) {
+						player.compass = Some(Compass {
+							layer: body.layer,
+							core: meta.core,
+							nearest: ground.nearest_biomes(body.pos.round())
+						});
+					}
+					wm.biome = player.compass.as_ref().map(|compass| BiomeMessage {
+						current: meta.biome,
+						nearest: compass.nearest.clone()
+					});
+				}
+				wm.dynamics = Some(DynamicMessage::new(
+					dynamics.iter()
+						.filter(|(layer, view)| *layer == body.layer && view_area.contains(view.pos.round()))
+						.map(|(_, view)| view.clone())
+						.collect()
+				));
 				wm.inventory = Some(body.inventory.view());
 				if !body.heard_sounds.is_empty() {
 					wm.sounds = Some(body.heard_sounds.clone());
@@ -305,12 +415,29 @@ impl World {
 			}
 			views.insert(playerid.clone(), wm);
 		}
-		self.ground.flush();
+		let maps = &self.ground.maps;
+		let regions = parallel::map(redraws, |(playerid, layer, area)| (playerid, layer, area, maps[&layer].base_region(area)));
+		let fields: Vec<(PlayerId, Area, Vec<Tile>)> = regions.into_iter()
+			.map(|(playerid, layer, area, region)| {
+				let tiles = self.ground.get(layer, self.time).apply_changes(region).map(|(_, tile)| tile).collect();
+				(playerid, area, tiles)
+			})
+			.collect();
+		for (playerid, section) in parallel::map(fields, |(playerid, area, tiles)| (playerid, draw_field(area, tiles))) {
+			if let Some(wm) = views.get_mut(&playerid) {
+				wm.section = Some(section);
+			}
+		}
+		for map in self.ground.maps.values_mut() {
+			map.flush();
+		}
 		views
 	}
 
+	/// View areas are aligned to the chunk grid of sections, so every redrawn section consists of whole chunks that the client can reuse
 	fn new_view_area(body_pos: Pos, view_area: &Option<Area>) -> (Area, Area) {
-		let core_area = Area::centered(body_pos, VIEW_AREA_SIZE);
+		let core_min = (body_pos - VIEW_AREA_SIZE / 2 + Pos::new(CHUNK_SIZE / 2, CHUNK_SIZE / 2)) / CHUNK_SIZE * CHUNK_SIZE;
+		let core_area = Area::new(core_min, VIEW_AREA_SIZE);
 		let Some(old_area) = view_area else {
 			return (core_area, core_area);
 		};
@@ -318,17 +445,17 @@ impl World {
 			return (core_area, core_area);
 		}
 		if body_pos.x <= old_area.min().x + EDGE_OFFSET {
-			let new_min = Pos::new(body_pos.x - VIEW_AREA_SIZE.x / 2, old_area.min().y);
+			let new_min = Pos::new(core_min.x, old_area.min().y);
 			(Area::new(new_min, VIEW_AREA_SIZE), Area::between(new_min, Pos::new(old_area.min().x, old_area.max().y)))
 		} else if body_pos.y <= old_area.min().y + EDGE_OFFSET {
-			let new_min = Pos::new(old_area.min().x, body_pos.y - VIEW_AREA_SIZE.y / 2);
+			let new_min = Pos::new(old_area.min().x, core_min.y);
 			(Area::new(new_min, VIEW_AREA_SIZE), Area::between(new_min, Pos::new(old_area.max().x, old_area.min().y)))
 		} else if body_pos.x >= old_area.max().x - EDGE_OFFSET {
-			let new_min = Pos::new(body_pos.x - VIEW_AREA_SIZE.x / 2, old_area.min().y);
+			let new_min = Pos::new(core_min.x, old_area.min().y);
 			let new_area = Area::new(new_min, VIEW_AREA_SIZE);
 			(new_area, Area::between(Pos::new(old_area.max().x, old_area.min().y), new_area.max()))
 		} else if body_pos.y >= old_area.max().y - EDGE_OFFSET {
-			let new_min = Pos::new(old_area.min().x, body_pos.y - VIEW_AREA_SIZE.y / 2);
+			let new_min = Pos::new(old_area.min().x, core_min.y);
 			let new_area = Area::new(new_min, VIEW_AREA_SIZE);
 			(new_area, Area::between(Pos::new(old_area.min().x, old_area.max().y), new_area.max()))
 		} else {
@@ -343,31 +470,86 @@ impl World {
 		WorldSave {
 			name: self.name.clone(),
 			time: self.time,
-			ground: self.ground.save(),
+			ground: self.ground.maps.get(&Layer::SURFACE).map(Map::save).unwrap_or_default(),
+			underground: self.ground.maps.iter()
+				.filter(|(layer, _)| **layer != Layer::SURFACE)
+				.map(|(layer, map)| (*layer, map.save()))
+				.collect(),
+			timers: self.ground.maps.iter()
+				.map(|(layer, map)| (*layer, map.timers()))
+				.collect(),
 			claims: self.claims.clone(),
-			seed: self.seed,
+			world_type: self.ground.world_type.clone(),
+			worldgen: self.ground.worldgen.clone(),
+			generator: self.ground.generator,
+			seed: self.ground.seed,
 		}
 	}
 	
 	pub fn load(save: WorldSave) -> World {
-		World {
+		let mut world = World {
 			name: save.name,
-			ground: Map::load(save.ground, save.time, save.seed),
+			ground: Layers {
+				maps: std::iter::once((Layer::SURFACE, save.ground))
+					.chain(save.underground)
+					.map(|(layer, changes)| (layer, Map::load(changes, save.world_type.basemap(layer, save.seed, &save.worldgen, save.generator), save.time)))
+					.collect(),
+				world_type: save.world_type,
+				worldgen: save.worldgen,
+				generator: save.generator,
+				seed: save.seed,
+				store: None
+			},
 			players: HashMap::new(),
 			creatures: Holder::new(),
 			time: save.time,
 			claims: save.claims,
-			seed: save.seed,
+		};
+		for (layer, timers) in save.timers {
+			world.ground.get(layer, save.time).load_timers(timers);
 		}
+		world
 	}
 }
 
 
-fn draw_field(area: Area, tiles: &mut Map) -> SectionMessage {
+/// The maps of all layers. Each map is created when it is first needed
+struct Layers {
+	maps: HashMap<Layer, Map>,
+	world_type: WorldType,
+	worldgen: WorldGenSettings,
+	generator: Generator,
+	seed: u32,
+	store: Option<Arc<dyn ChunkStore>>,
+}
+
+impl Layers {
+	fn get(&mut self, layer: Layer, time: Tickstamp) -> &mut Map {
+		self.maps.entry(layer).or_insert_with(|| {
+			let mut map = Map::with_basemap(self.world_type.basemap(layer, self.seed, &self.worldgen, self.generator), time);
+			if let Some(store) = &self.store {
+				map.set_store(layer, store.clone());
+			}
+			map
+		})
+	}
+}
+
+fn enter_portal(ground: &mut Map, creature: &mut Creature, oldpos: Pos) {
+	let pos = creature.pos.round();
+	if pos != oldpos {
+		if let Some(layer) = ground.cell(pos).structure.portal() {
+			creature.layer = layer;
+		}
+	}
+}
+
+/// The tiles are in the order of area.iter()
+fn draw_field(area: Area, tiles: Vec<Tile>) -> SectionMessage {
 	// println!("redrawing field");
 	let mut values :Vec<usize> = Vec::with_capacity((area.size().x * area.size().y) as usize);
 	let mut mapping: Vec<Vec<Sprite>> = Vec::new();
-	for (pos, tile) in tiles.load_area(area) {
+	for tile in tiles {
 		let mut tile_sprites = Vec::new();
 		tile_sprites.append(&mut tile.sprites());
 		values.push(
@@ -401,8 +583,134 @@ pub enum PlayerError {
 pub struct WorldSave {
 	name: String,
 	time: Tickstamp,
+	/// Changes that are not saved in chunks, either from older saves or from worlds without chunk store
+	#[serde(default)]
 	ground: MapSave,
+	#[serde(default)]
+	underground: Vec<(Layer, MapSave)>,
+	#[serde(default)]
+	timers: Vec<(Layer, Vec<Timer>)>,
 	claims: HashMap<PlayerId, Pos>,
+	#[serde(default)]
+	world_type: WorldType,
+	#[serde(default)]
+	worldgen: WorldGenSettings,
+	#[serde(default)]
+	generator: Generator,
 	seed: u32
 }
 
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{tile::Structure, worldmessages::{MessageCache, SectionEncoding}};
+
+	fn world_with_players(positions: &[(&str, Vec2)]) -> World {
+		let mut world = World::new("test".to_string(), 9876, WorldType::Infinite, WorldGenSettings::default());
+		for (name, pos) in positions {
+			world.add_player(&PlayerId(name.to_string()), PlayerSave::new(*pos)).unwrap();
+		}
+		world.view();
+		world
+	}
+
+	#[test]
+	fn far_changes_are_not_sent() {
+		let mut world = world_with_players(&[("near", Vec2::new(0.0, 0.0)), ("far", Vec2::new(1000.0, 0.0))]);
+		let pos = Pos::new(3, 4);
+		world.map(Layer::SURFACE).set_structure(pos, Structure::Wall);
+		let views = world.view();
+		assert!(views[&PlayerId("near".to_string())].change.as_ref().unwrap().iter().any(|(p, _)| *p == pos));
+		assert_eq!(views[&PlayerId("far".to_string())].change, None);
+	}
+
+	#[test]
+	fn only_players_in_view_are_sent() {
+		let mut world = world_with_players(&[("a", Vec2::new(0.0, 0.0)), ("b", Vec2::new(10.0, 0.0)), ("c", Vec2::new(1000.0, 0.0))]);
+		let views = world.view();
+		let dynamics = |player: &str| views[&PlayerId(player.to_string())].dynamics.as_ref().unwrap().set.len();
+		assert_eq!(dynamics("a"), 2);
+		assert_eq!(dynamics("b"), 2);
+		assert_eq!(dynamics("c"), 1);
+	}
+
+	#[test]
+	fn sections_are_aligned_to_chunks() {
+		let mut world = world_with_players(&[("a", Vec2::new(3.0, -7.0))]);
+		let player = PlayerId("a".to_string());
+		let body = world.players[&player].body;
+		let aligned = |pos: Pos| pos.x.rem_euclid(CHUNK_SIZE) == 0 && pos.y.rem_euclid(CHUNK_SIZE) == 0;
+		let mut sections = 0;
+		for step in 0..200 {
+			world.creatures.get_mut(&body).unwrap().pos = Vec2::new(3.0 - step as f32, -7.0 + step as f32 * 0.5);
+			if let Some(section) = world.view().remove(&player).unwrap().section {
+				assert!(aligned(section.area.min()) && aligned(section.area.size()), "{:?}", section.area);
+				sections += 1;
+			}
+			assert!(world.players[&player].view_area.unwrap().contains(world.creatures.get(&body).unwrap().pos.round()));
+		}
+		assert!(sections > 2);
+	}
+
+	#[test]
+	fn creatures_leaving_the_view_are_removed() {
+		let mut world = world_with_players(&[("a", Vec2::new(0.0, 0.0)), ("b", Vec2::new(10.0, 0.0))]);
+		let a = PlayerId("a".to_string());
+		let b = world.players[&PlayerId("b".to_string())].body;
+		let mut cache = MessageCache::default();
+		let mut message = world.view().remove(&a).unwrap();
+		cache.trim(&a, &mut message);
+		assert!(message.dynamics.unwrap().set.iter().any(|view| view.id == b));
+		world.creatures.get_mut(&b).unwrap().pos = Vec2::new(1000.0, 0.0);
+		let mut message = world.view().remove(&a).unwrap();
+		cache.trim(&a, &mut message);
+		assert_eq!(message.dynamics.unwrap().remove, vec![b]);
+	}
+
+	#[test]
+	fn missing_chunks_are_sent_again() {
+		let mut world = world_with_players(&[("a", Vec2::new(0.0, 0.0))]);
+		let a = PlayerId("a".to_string());
+		let mut cache = MessageCache::default();
+		cache.set_encoding(&a, SectionEncoding::Chunks);
+		let redraw = |world: &mut World, cache: &mut MessageCache| {
+			world.redraw_player(&a).unwrap();
+			let mut message = world.view().remove(&a).unwrap();
+			cache.trim(&a, &mut message);
+			message.chunks.unwrap()
+		};
+		let chunks = redraw(&mut world, &mut cache);
+		assert!(chunks.iter().all(|chunk| chunk.field.is_some()));
+		cache.forget_chunks(&a, &[chunks[0].id.clone()]);
+		let chunks = redraw(&mut world, &mut cache);
+		assert!(chunks[0].field.is_some());
+		assert!(chunks[1..].iter().all(|chunk| chunk.field.is_none()));
+	}
+
+	#[test]
+	fn saves_without_generator_use_the_legacy_generator() {
+		let world = World::new("test".to_string(), 9876, WorldType::Infinite, WorldGenSettings::default());
+		assert_eq!(world.ground.generator, Generator::V1);
+		let mut save = serde_json::to_value(world.save()).unwrap();
+		assert_eq!(World::load(serde_json::from_value(save.clone()).unwrap()).ground.generator, Generator::V1);
+		save.as_object_mut().unwrap().remove("generator");
+		assert_eq!(World::load(serde_json::from_value(save).unwrap()).ground.generator, Generator::V0);
+	}
+
+	#[test]
+	fn compass_follows_the_biome_cell() {
+		let mut world = world_with_players(&[("a", Vec2::new(0.0, 0.0))]);
+		let player = PlayerId("a".to_string());
+		let compass = |world: &World| world.players[&player].compass.clone().unwrap();
+		let Compass{core, nearest, ..} = compass(&world);
+		let biome = world.view()[&player].biome.clone().unwrap();
+		assert_eq!(biome.nearest, nearest);
+		let body = world.players[&player].body;
+		world.creatures.get_mut(&body).unwrap().pos = Vec2::new(1000.0, 1000.0);
+		world.view();
+		let far = compass(&world);
+		assert_ne!(far.core, core);
+		assert_ne!(far.nearest, nearest);
+	}
+}
@@ -0,0 +1,172 @@
+
+use std::fs;
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+use crate::{
+	basemap::Biome,
+	errors::Result,
+	aerr,
+};
+
+/// The parameters of the generator for infinite maps.
+/// Fields that are missing in a settings file keep their default value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorldGenSettings {
+	/// The distance between the cores of neighbouring biomes
+	pub biome_size: i32,
+	/// How far biomes mix at their edges
+	pub edge_size: i32,
+	pub biomes: BiomeWeights,
+	pub heightmaps: Octaves,
+}
+
+impl Default for WorldGenSettings {
+	fn default() -> Self {
+		Self {
+			biome_size: 48,
+			edge_size: 12,
+			biomes: BiomeWeights::default(),
+			heightmaps: Octaves::default(),
+		}
+	}
+}
+
+impl WorldGenSettings {
+	pub fn load(path: &Path) -> Result<Self> {
+		let text = fs::read_to_string(path)?;
+		Self::parse(&text)
+	}
+
+	pub fn parse(text: &str) -> Result<Self> {
+		let settings: Self = json5::from_str(text)?;
+		if settings.biome_size < 4 || settings.edge_size < 1 {
+			return Err(aerr!("biome_size should be at least 4 and edge_size at least 1"));
+		}
+		for (name, weights) in settings.biomes.lists() {
+			if weights.iter().map(|(_, weight)| weight).sum::<u32>() == 0 {
+				return Err(aerr!("the {} biome weights should not all be 0", name));
+			}
+		}
+		for (name, octaves) in settings.heightmaps.lists() {
+			if octaves.is_empty() || octaves.iter().any(|(scale, _)| *scale < 1) {
+				return Err(aerr!("the {} heightmap needs octaves with a scale of at least 1", name));
+			}
+		}
+		Ok(settings)
+	}
+}
+
+/// The chance of each biome for each kind of climate
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BiomeWeights {
+	pub cold: Vec<(Biome, u32)>,
+	pub hot: Vec<(Biome, u32)>,
+	pub wet: Vec<(Biome, u32)>,
+	pub temperate: Vec<(Biome, u32)>,
+}
+
+impl Default for BiomeWeights {
+	fn default() -> Self {
+		Self {
+			cold: vec![
+				(Biome::Highland, 10),
+				(Biome::Rocks, 3),
+				(Biome::Snowfield, 1),
+			],
+			hot: vec![
+				(Biome::Desert, 10),
+				(Biome::Rocks, 2),
+				(Biome::Field, 1),
+			],
+			wet: vec![
+				(Biome::Field, 5),
+				(Biome::Forest, 10),
+				(Biome::Lake, 8),
+				(Biome::Bog, 8),
+			],
+			temperate: vec![
+				(Biome::Field, 10),
+				(Biome::Forest, 10),
+				(Biome::Lake, 3),
+				(Biome::Rocks, 5),
+				(Biome::Highland, 2),
+				(Biome::Bog, 2),
+			],
+		}
+	}
+}
+
+impl BiomeWeights {
+	fn lists(&self) -> [(&str, &[(Biome, u32)]); 4] {
+		[("cold", &self.cold), ("hot", &self.hot), ("wet", &self.wet), ("temperate", &self.temperate)]
+	}
+}
+
+/// The scale and weight of each octave of the heightmaps
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Octaves {
+	pub lake: Vec<(i32, f32)>,
+	pub rock: Vec<(i32, f32)>,
+	pub bog: Vec<(i32, f32)>,
+	pub reed: Vec<(i32, f32)>,
+	pub river: Vec<(i32, f32)>,
+	pub dune: Vec<(i32, f32)>,
+	pub temperature: Vec<(i32, f32)>,
+	pub moisture: Vec<(i32, f32)>,
+}
+
+impl Default for Octaves {
+	fn default() -> Self {
+		Self {
+			lake: vec![(3, 0.12), (5, 0.20), (7, 0.26), (11, 0.42)],
+			rock: vec![(3, 0.12), (5, 0.20), (7, 0.26), (11, 0.42)],
+			bog: vec![(3, 0.25), (5, 0.35), (7, 0.4)],
+			reed: vec![(7, 0.5), (11, 0.5)],
+			river: vec![(5, 0.2), (13, 0.3), (29, 0.5)],
+			dune: vec![(2, 0.3), (5, 0.7)],
+			temperature: vec![(2, 0.2), (4, 0.3), (8, 0.5)],
+			moisture: vec![(2, 0.3), (5, 0.7)],
+		}
+	}
+}
+
+impl Octaves {
+	fn lists(&self) -> [(&str, &[(i32, f32)]); 8] {
+		[
+			("lake", &self.lake),
+			("rock", &self.rock),
+			("bog", &self.bog),
+			("reed", &self.reed),
+			("river", &self.river),
+			("dune", &self.dune),
+			("temperature", &self.temperature),
+			("moisture", &self.moisture),
+		]
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn missing_settings_are_default() {
+		let settings = WorldGenSettings::parse("{biome_size: 64, biomes: {wet: [['lake', 20], ['forest', 1]]}}").unwrap();
+		assert_eq!(settings.biome_size, 64);
+		assert_eq!(settings.edge_size, 12);
+		assert_eq!(settings.biomes.wet, vec![(Biome::Lake, 20), (Biome::Forest, 1)]);
+		assert_eq!(settings.biomes.cold, BiomeWeights::default().cold);
+		assert_eq!(settings.heightmaps, Octaves::default());
+	}
+
+	#[test]
+	fn invalid_settings_are_rejected() {
+		assert!(WorldGenSettings::parse("{edge_size: 0}").is_err());
+		assert!(WorldGenSettings::parse("{biomes: {hot: []}}").is_err());
+		assert!(WorldGenSettings::parse("{heightmaps: {reed: [[0, 1.0]]}}").is_err());
+	}
+}
@@ -1,14 +1,15 @@
 
-use std::collections::HashMap;
-use serde_json::{Value, json};
-use serde::Serialize;
+use std::collections::{HashMap, BTreeMap};
+use serde::{Serialize, Serializer, Deserialize, ser::SerializeSeq};
 use crate::{
 	Pos,
-	pos::Area,
+	pos::{Area, Layer},
 	Sprite,
 	PlayerId,
 	vec2::Vec2,
-	creature::CreatureView
+	creature::{CreatureView, CreatureId},
+	basemap::Biome,
+	util::fnv1a,
 };
 
 macro_rules! worldmessages {
@@ -43,14 +44,27 @@ macro_rules! worldmessages {
 				true $( && self.$name.is_none())*
 			}
 			
-			pub fn to_json(&self) -> Value {
-				let mut updates: Vec<Value> = Vec::new();
+		}
+
+		/// Serialized as ["world", [[name, update], ...]] with only the updates that are present
+		impl Serialize for WorldMessage {
+			fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+				("world", Updates(self)).serialize(serializer)
+			}
+		}
+
+		struct Updates<'a>(&'a WorldMessage);
+
+		impl Serialize for Updates<'_> {
+			fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+				let len = 0 $( + self.0.$name.is_some() as usize)*;
+				let mut seq = serializer.serialize_seq(Some(len))?;
 				$(
-					if let Some(update) = &self.$name {
-						updates.push(json!([$strname, update]));
+					if let Some(update) = &self.0.$name {
+						seq.serialize_element(&($strname, update))?;
 					}
 				)*
-				json!(["world", updates])
+				seq.end()
 			}
 		}
 	}
@@ -63,20 +77,47 @@ worldmessages!(
 	sounds, SoundMessage, "messages", false;
 	viewarea, ViewAreaMessage, "viewarea", true;
 	section, SectionMessage, "section", true;
-	dynamics, DynamicMessage, "dynamics", true;
+	chunks, ChunksMessage, "chunks", false;
+	dynamics, DynamicMessage, "dynamics", false;
+	layer, Layer, "layer", true;
+	biome, BiomeMessage, "biome", true;
 );
 
 
 pub type ChangeMessage = Vec<(Pos, Vec<Sprite>)>;
 pub type InventoryMessage = (Vec<(String, Option<usize>)>, usize);
 pub type SoundMessage = Vec<(SoundType, String)>;
-pub type DynamicMessage = Vec<CreatureView>;
+pub type ChunksMessage = Vec<Chunk>;
+
+/// How the client wants to receive sections of the map
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all="lowercase")]
+pub enum SectionEncoding {
+	/// The whole section as one "section" message with a flat field
+	#[default]
+	Plain,
+	/// The section split into run-length encoded chunks in a "chunks" message.
+	/// Chunks that the client has received before are only sent by id
+	Chunks,
+}
+
+/// Chunks are aligned to a grid of this size so revisited areas give the same chunks
+pub const CHUNK_SIZE: i32 = 32;
+/// The client keeps this many of the chunks it used most recently (maxChunks in display.js)
+const MAX_KNOWN_CHUNKS: usize = 1024;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
 pub struct ViewAreaMessage {
 	pub area: Area
 }
 
+/// The biome the player is in and where the nearest biome of each type is
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BiomeMessage {
+	pub current: Biome,
+	pub nearest: Vec<(Biome, Pos)>
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
 pub struct SectionMessage {
 	pub field: Vec<usize>,
@@ -84,6 +125,109 @@ pub struct SectionMessage {
 	pub area: Area
 }
 
+impl SectionMessage {
+	/// Split the section into chunks along the chunk grid.
+	/// Chunks that the client still knows only get their area and id
+	fn chunks(&self, known: &mut KnownChunks) -> ChunksMessage {
+		let min = self.area.min() / CHUNK_SIZE;
+		let max = (self.area.max() - Pos::new(1, 1)) / CHUNK_SIZE;
+		let mut chunks = Vec::new();
+		for cy in min.y..=max.y {
+			for cx in min.x..=max.x {
+				let grid_area = Area::new(Pos::new(cx, cy) * CHUNK_SIZE, Pos::new(CHUNK_SIZE, CHUNK_SIZE));
+				let area = Area::between(
+					Pos::new(grid_area.min().x.max(self.area.min().x), grid_area.min().y.max(self.area.min().y)),
+					Pos::new(grid_area.max().x.min(self.area.max().x), grid_area.max().y.min(self.area.max().y))
+				);
+				let chunk = self.chunk(area);
+				chunks.push(if known.touch(&chunk.id) { chunk.reference() } else { chunk });
+			}
+		}
+		chunks
+	}
+
+	fn chunk(&self, area: Area) -> Chunk {
+		let mut mapping: Vec<Vec<Sprite>> = Vec::new();
+		let mut local: HashMap<usize, usize> = HashMap::new();
+		let mut field: Vec<(usize, usize)> = Vec::new();
+		for pos in area.iter() {
+			let offset = pos - self.area.min();
+			let index = self.field[(offset.y * self.area.size().x + offset.x) as usize];
+			let value = *local.entry(index).or_insert_with(|| {
+				mapping.push(self.mapping[index].clone());
+				mapping.len() - 1
+			});
+			match field.last_mut() {
+				Some((last, count)) if *last == value => { *count += 1; }
+				_ => { field.push((value, 1)); }
+			}
+		}
+		let content = serde_json::to_vec(&(area.size(), &field, &mapping)).unwrap();
+		Chunk {
+			area,
+			id: format!("{:x}-{:016x}", content.len(), fnv1a(&content)),
+			field: Some(field),
+			mapping: Some(mapping)
+		}
+	}
+}
+
+/// A part of a section. The field is run-length encoded as (value, count) pairs.
+/// The id only depends on the content, so chunks with the same terrain share it
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct Chunk {
+	pub area: Area,
+	pub id: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub field: Option<Vec<(usize, usize)>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub mapping: Option<Vec<Vec<Sprite>>>,
+}
+
+impl Chunk {
+	fn reference(self) -> Self {
+		Self {
+			field: None,
+			mapping: None,
+			..self
+		}
+	}
+}
+
+/// Creatures that are new or changed, and the ids of creatures that are gone.
+/// The world puts all creatures in view in it; the MessageCache turns that into the changes since the last message.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct DynamicMessage {
+	pub set: Vec<CreatureView>,
+	pub remove: Vec<CreatureId>,
+}
+
+impl DynamicMessage {
+	pub fn new(creatures: Vec<CreatureView>) -> Self {
+		Self {
+			set: creatures,
+			remove: Vec::new()
+		}
+	}
+
+	fn changes_since(&self, previous: &DynamicMessage) -> Self {
+		Self {
+			set: self.set.iter()
+				.filter(|view| !previous.set.contains(view))
+				.cloned()
+				.collect(),
+			remove: previous.set.iter()
+				.map(|view| view.id)
+				.filter(|id| !self.set.iter().any(|view| view.id == *id))
+				.collect()
+		}
+	}
+
+	fn is_empty(&self) -> bool {
+		self.set.is_empty() && self.remove.is_empty()
+	}
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
 #[serde(rename_all="lowercase")]
 pub enum SoundType {
@@ -92,26 +236,368 @@ pub enum SoundType {
 }
 
 
+/// The ids of the chunks that the client has, in the order they were last used.
+/// The client forgets the least recently used chunks in the same way, so both agree on which chunks it has
+#[derive(Debug, Clone, Default)]
+struct KnownChunks {
+	uses: HashMap<String, u64>,
+	order: BTreeMap<u64, String>,
+	count: u64,
+}
+
+impl KnownChunks {
+	/// Mark the chunk as the most recently used one. Returns whether the client already had it
+	fn touch(&mut self, id: &str) -> bool {
+		self.count += 1;
+		let known = if let Some(last) = self.uses.insert(id.to_string(), self.count) {
+			self.order.remove(&last);
+			true
+		} else {
+			false
+		};
+		self.order.insert(self.count, id.to_string());
+		while self.uses.len() > MAX_KNOWN_CHUNKS {
+			if let Some((_, oldest)) = self.order.pop_first() {
+				self.uses.remove(&oldest);
+			}
+		}
+		known
+	}
+	
+	fn forget(&mut self, id: &str) {
+		if let Some(last) = self.uses.remove(id) {
+			self.order.remove(&last);
+		}
+	}
+}
+
+#[derive(Debug, Clone, Default)]
+struct ClientChunks {
+	encoding: SectionEncoding,
+	known: KnownChunks,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct MessageCache {
-	cache: HashMap<PlayerId, WorldMessage>
+	cache: HashMap<PlayerId, WorldMessage>,
+	clients: HashMap<PlayerId, ClientChunks>,
 }
 
 impl MessageCache {
+
+	pub fn set_encoding(&mut self, player: &PlayerId, encoding: SectionEncoding){
+		self.clients.insert(player.clone(), ClientChunks { encoding, known: KnownChunks::default() });
+	}
 	
 	pub fn trim(&mut self, player: &PlayerId, msg: &mut WorldMessage){
 		if let Some(cached) = self.cache.get_mut(player){
+			let full = msg.clone();
 			msg.remove_old(cached);
-			cached.add(msg);
+			if let (Some(dynamics), Some(known)) = (&msg.dynamics, &cached.dynamics) {
+				let changes = dynamics.changes_since(known);
+				msg.dynamics = if changes.is_empty() { None } else { Some(changes) };
+			}
+			cached.add(&full);
 		} else {
 			self.cache.insert(player.clone(), msg.clone());
 		}
+		self.encode_section(player, msg);
+	}
+	
+	fn encode_section(&mut self, player: &PlayerId, msg: &mut WorldMessage){
+		let Some(client) = self.clients.get_mut(player) else {
+			return;
+		};
+		if client.encoding == SectionEncoding::Chunks {
+			if let Some(section) = msg.section.take() {
+				msg.chunks = Some(section.chunks(&mut client.known));
+			}
+		}
+	}
+	
+	/// The client lost these chunks, so they are sent in full when they are used again.
+	/// The last section is forgotten too, so that redrawing the same section isn't left out
+	pub fn forget_chunks(&mut self, player: &PlayerId, ids: &[String]){
+		if let Some(cached) = self.cache.get_mut(player) {
+			cached.viewarea = None;
+			cached.section = None;
+		}
+		if let Some(client) = self.clients.get_mut(player) {
+			for id in ids {
+				client.known.forget(id);
+			}
+		}
 	}
 	
 	pub fn remove(&mut self, player: &PlayerId){
 		self.cache.remove(player);
+		self.clients.remove(player);
 	}
 }
 
 
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::{Value, json};
+	use crate::server::Encoding;
+
+	fn view(id: usize, x: f32) -> CreatureView {
+		CreatureView {
+			id: CreatureId(id),
+			sprite: Sprite::PlayerDefault,
+			pos: Vec2::new(x, 0.0),
+			name: None
+		}
+	}
+
+	fn trimmed(cache: &mut MessageCache, creatures: Vec<CreatureView>) -> Option<DynamicMessage> {
+		let mut msg = WorldMessage {
+			dynamics: Some(DynamicMessage::new(creatures)),
+			..Default::default()
+		};
+		cache.trim(&PlayerId("p".to_string()), &mut msg);
+		msg.dynamics
+	}
+
+	#[test]
+	fn dynamics_are_sent_as_changes() {
+		let mut cache = MessageCache::default();
+		assert_eq!(trimmed(&mut cache, vec![view(1, 0.0), view(2, 0.0)]).unwrap().set.len(), 2);
+		assert_eq!(trimmed(&mut cache, vec![view(1, 0.0), view(2, 0.0)]), None);
+		assert_eq!(
+			trimmed(&mut cache, vec![view(2, 1.0), view(3, 0.0)]),
+			Some(DynamicMessage {
+				set: vec![view(2, 1.0), view(3, 0.0)],
+				remove: vec![CreatureId(1)]
+			})
+		);
+	}
+
+	#[test]
+	fn sections_split_into_known_chunks() {
+		let area = Area::new(Pos::new(-40, 10), Pos::new(70, 30));
+		let section = SectionMessage {
+			field: area.iter().map(|pos| (pos.x > 0 && pos.y > 20) as usize).collect(),
+			mapping: vec![vec![Sprite::Grass1], vec![Sprite::Water]],
+			area
+		};
+		let mut known = KnownChunks::default();
+		let chunks = section.chunks(&mut known);
+		assert_eq!(chunks.iter().map(|chunk| chunk.area.surface()).sum::<i32>(), area.surface());
+		// identical chunks are only sent in full once, like the client cache
+		let mut full: HashMap<&String, &Chunk> = HashMap::new();
+		for chunk in chunks.iter() {
+			let content = *full.entry(&chunk.id).or_insert(chunk);
+			let mapping = content.mapping.as_ref().unwrap();
+			let cells: Vec<&Vec<Sprite>> = content.field.as_ref().unwrap().iter()
+				.flat_map(|(value, count)| std::iter::repeat(&mapping[*value]).take(*count))
+				.collect();
+			let expected: Vec<&Vec<Sprite>> = chunk.area.iter()
+				.map(|pos| &section.mapping[(pos.x > 0 && pos.y > 20) as usize])
+				.collect();
+			assert_eq!(cells, expected);
+		}
+		let again = section.chunks(&mut known);
+		assert!(again.iter().all(|chunk| chunk.field.is_none() && chunk.mapping.is_none()));
+		assert_eq!(again.iter().map(|chunk| &chunk.id).collect::<Vec<_>>(), chunks.iter().map(|chunk| &chunk.id).collect::<Vec<_>>());
+	}
+
+	#[test]
+	fn chunk_ids_are_stable() {
+		// clients can keep chunks across server versions, so the id may only change with the content
+		let area = Area::new(Pos::new(-2, 3), Pos::new(2, 2));
+		let section = |field| SectionMessage {
+			field,
+			mapping: vec![vec![Sprite::Grass1], vec![Sprite::Water]],
+			area
+		};
+		let id = |field| section(field).chunks(&mut KnownChunks::default())[0].id.clone();
+		assert_eq!(id(vec![0, 0, 1, 0]), "32-447b9221a6c11202");
+		assert_ne!(id(vec![0, 1, 1, 0]), id(vec![0, 0, 1, 0]));
+	}
+
+	#[test]
+	fn least_recently_used_chunks_are_forgotten() {
+		let mut known = KnownChunks::default();
+		for n in 0..MAX_KNOWN_CHUNKS {
+			assert!(!known.touch(&n.to_string()));
+		}
+		assert!(known.touch("0"));
+		assert!(!known.touch("new"));
+		// "0" was used again, so "1" was the least recently used one
+		assert!(known.touch("0"));
+		assert!(!known.touch("1"));
+	}
+
+	#[test]
+	fn forgotten_chunks_are_sent_again() {
+		let mut known = KnownChunks::default();
+		assert!(!known.touch("a"));
+		assert!(!known.touch("b"));
+		known.forget("a");
+		assert!(!known.touch("a"));
+		assert!(known.touch("b"));
+	}
+
+	#[test]
+	fn world_messages_round_trip() {
+		let area = Area::new(Pos::new(-2, 3), Pos::new(2, 2));
+		let section = SectionMessage {
+			field: vec![0, 0, 1, 0],
+			mapping: vec![vec![Sprite::Grass1], vec![Sprite::Water]],
+			area
+		};
+		let msg = WorldMessage {
+			pos: Some(Vec2::new(1.5, -3.25)),
+			change: Some(vec![(Pos::new(4, -5), vec![Sprite::Water])]),
+			inventory: Some((vec![("stone".to_string(), Some(3)), ("axe".to_string(), None)], 1)),
+			sounds: Some(vec![(SoundType::BuildError, "can't build here".to_string()), (SoundType::Explain, "a stone".to_string())]),
+			viewarea: Some(ViewAreaMessage{area}),
+			chunks: Some(section.chunks(&mut KnownChunks::default())),
+			section: Some(section),
+			dynamics: Some(DynamicMessage {
+				set: vec![view(1, 2.5), CreatureView{name: Some("alice".to_string()), ..view(2, 0.0)}],
+				remove: vec![CreatureId(3)]
+			}),
+			layer: Some(Layer(-1)),
+			biome: Some(BiomeMessage{current: Biome::Forest, nearest: vec![(Biome::Lake, Pos::new(100, -20))]}),
+		};
+		let expected = serde_json::to_value(&msg).unwrap();
+		assert_eq!(expected[0], "world");
+		assert_eq!(expected[1].as_array().unwrap().len(), 10);
+		for encoding in [Encoding::Json, Encoding::MessagePack] {
+			let data = encoding.encode(&msg).unwrap();
+			assert_eq!(encoding.decode::<serde_json::Value>(&data).unwrap(), expected);
+		}
+		assert_eq!(serde_json::to_string(&WorldMessage::default()).unwrap(), r#"["world",[]]"#);
+	}
+
+	/// Both encodings decode to the json shape that the web client reads
+	fn assert_encodes_as(msg: WorldMessage, update: Value) {
+		let expected = json!(["world", [update]]);
+		for encoding in [Encoding::Json, Encoding::MessagePack] {
+			let data = encoding.encode(&msg).unwrap();
+			assert_eq!(encoding.decode::<Value>(&data).unwrap(), expected, "{:?}", encoding);
+		}
+	}
+
+	fn test_section() -> SectionMessage {
+		SectionMessage {
+			field: vec![0, 0, 1, 0],
+			mapping: vec![vec![Sprite::Grass1], vec![Sprite::Water]],
+			area: Area::new(Pos::new(-2, 3), Pos::new(2, 2))
+		}
+	}
+
+	#[test]
+	fn playerpos_is_encoded() {
+		assert_encodes_as(
+			WorldMessage { pos: Some(Vec2::new(1.5, -3.25)), ..Default::default() },
+			json!(["playerpos", [1.5, -3.25]])
+		);
+	}
+
+	#[test]
+	fn changecells_are_encoded() {
+		assert_encodes_as(
+			WorldMessage { change: Some(vec![(Pos::new(4, -5), vec![Sprite::Water, Sprite::Reed])]), ..Default::default() },
+			json!(["changecells", [[[4, -5], ["water", "reed"]]]])
+		);
+	}
+
+	#[test]
+	fn inventory_is_encoded() {
+		assert_encodes_as(
+			WorldMessage { inventory: Some((vec![("stone".to_string(), Some(3)), ("axe".to_string(), None)], 1)), ..Default::default() },
+			json!(["inventory", [[["stone", 3], ["axe", null]], 1]])
+		);
+	}
+
+	#[test]
+	fn sounds_are_encoded() {
+		assert_encodes_as(
+			WorldMessage {
+				sounds: Some(vec![(SoundType::BuildError, "can't build here".to_string()), (SoundType::Explain, "a stone".to_string())]),
+				..Default::default()
+			},
+			json!(["messages", [["builderror", "can't build here"], ["explain", "a stone"]]])
+		);
+	}
+
+	#[test]
+	fn viewarea_is_encoded() {
+		assert_encodes_as(
+			WorldMessage { viewarea: Some(ViewAreaMessage{area: Area::new(Pos::new(-2, 3), Pos::new(2, 2))}), ..Default::default() },
+			json!(["viewarea", {"area": {"x": -2, "y": 3, "w": 2, "h": 2}}])
+		);
+	}
+
+	#[test]
+	fn section_is_encoded() {
+		assert_encodes_as(
+			WorldMessage { section: Some(test_section()), ..Default::default() },
+			json!(["section", {
+				"area": {"x": -2, "y": 3, "w": 2, "h": 2},
+				"field": [0, 0, 1, 0],
+				"mapping": [["grass1"], ["water"]]
+			}])
+		);
+	}
+
+	#[test]
+	fn chunks_are_encoded() {
+		let mut known = KnownChunks::default();
+		let section = test_section();
+		assert_encodes_as(
+			WorldMessage { chunks: Some(section.chunks(&mut known)), ..Default::default() },
+			json!(["chunks", [{
+				"id": "32-447b9221a6c11202",
+				"area": {"x": -2, "y": 3, "w": 2, "h": 2},
+				"field": [[0, 2], [1, 1], [0, 1]],
+				"mapping": [["grass1"], ["water"]]
+			}]])
+		);
+		// known chunks only have their id and area
+		assert_encodes_as(
+			WorldMessage { chunks: Some(section.chunks(&mut known)), ..Default::default() },
+			json!(["chunks", [{"id": "32-447b9221a6c11202", "area": {"x": -2, "y": 3, "w": 2, "h": 2}}]])
+		);
+	}
+
+	#[test]
+	fn dynamics_are_encoded() {
+		assert_encodes_as(
+			WorldMessage {
+				dynamics: Some(DynamicMessage {
+					set: vec![view(1, 2.5), CreatureView{name: Some("alice".to_string()), ..view(2, 0.0)}],
+					remove: vec![CreatureId(3)]
+				}),
+				..Default::default()
+			},
+			json!(["dynamics", {
+				"set": [{"i": 1, "s": "player", "p": [2.5, 0.0]}, {"i": 2, "s": "player", "p": [0.0, 0.0], "n": "alice"}],
+				"remove": [3]
+			}])
+		);
+	}
 
+	#[test]
+	fn layer_is_encoded() {
+		assert_encodes_as(
+			WorldMessage { layer: Some(Layer(-1)), ..Default::default() },
+			json!(["layer", -1])
+		);
+	}
+
+	#[test]
+	fn biome_is_encoded() {
+		assert_encodes_as(
+			WorldMessage {
+				biome: Some(BiomeMessage{current: Biome::Forest, nearest: vec![(Biome::Lake, Pos::new(100, -20))]}),
+				..Default::default()
+			},
+			json!(["biome", {"current": "forest", "nearest": [["lake", [100, -20]]]}])
+		);
+	}
+}
@@ -152,6 +152,9 @@ class DrawBuffer {
 
 class Display {
 	tileSize = 8;
+	// the server forgets the same least recently used chunks (MAX_KNOWN_CHUNKS in worldmessages.rs).
+	// Chunks that still go missing, for example after a lost message, are asked for again
+	maxChunks = 1024;
 
 	constructor(canvas, spritemap, fuzzSprite) {
 		this.canvas = canvas;
@@ -164,11 +167,14 @@ class Display {
 		this.centerX = 0;
 		this.centerY = 0;
 		this.borders = new Map();
+		this.chunks = new Map();
 		this.width = 0;
 		this.height = 0;
 		this.scale = 4;
 		this.init = false;
 		this.fuzzSprite = fuzzSprite;
+		this.underground = false;
+		this.labels = [];
 	}
 
 	setViewArea(area){
@@ -218,6 +224,37 @@ class Display {
 		this.init = true
 	}
 
+	// returns the ids of the chunks that were referred to but are not known, so the server can send them again
+	drawChunks(chunks){
+		let missing = [];
+		for (let chunk of chunks) {
+			if (chunk.field) {
+				// expand the run-length encoded field once and keep it for when the chunk comes back
+				let cells = [];
+				for (let [value, count] of chunk.field) {
+					for (let i=0; i<count; ++i) {
+						cells.push(value);
+					}
+				}
+				this.chunks.set(chunk.id, {cells: cells, mapping: chunk.mapping});
+			}
+			let content = this.chunks.get(chunk.id);
+			if (!content) {
+				console.error("unknown chunk", chunk.id);
+				missing.push(chunk.id);
+				continue;
+			}
+			// a map iterates in insertion order, so reinserting keeps the least recently used chunk first
+			this.chunks.delete(chunk.id);
+			this.chunks.set(chunk.id, content);
+			while (this.chunks.size > this.maxChunks) {
+				this.chunks.delete(this.chunks.keys().next().value);
+			}
+			this.drawSection(chunk.area.w, chunk.area.h, chunk.area.x, chunk.area.y, content.cells, content.mapping);
+		}
+		return missing;
+	}
+
 	changeTiles(tiles) {
 		if (!this.init) {
 			return;
@@ -242,8 +279,12 @@ class Display {
 
 	drawDynamics(entities) {
 		this.buffers.creatures.clear();
+		this.labels = [];
 		for (let entity of entities) {
 			this._drawSprite(entity.s, entity.p[0], entity.p[1]);
+			if (entity.n) {
+				this.labels.push({text: entity.n, x: entity.p[0], y: entity.p[1]});
+			}
 		}
 	}
 
@@ -288,6 +329,10 @@ class Display {
 		return this.borders.get(hashpos(x, y));
 	}
 
+	setLayer(layer) {
+		this.underground = layer < 0;
+	}
+
 	setCenter(x, y) {
 		this.centerX = x;
 		this.centerY = y;
@@ -331,6 +376,20 @@ class Display {
 				buffer.canvas.height * tileSize / buffer.resolution
 			);
 		}
+		if (this.underground) {
+			this.outerCtx.fillStyle = "rgba(0, 0, 0, 0.45)";
+			this.outerCtx.fillRect(0, 0, this.canvas.width, this.canvas.height);
+		}
+		this.outerCtx.font = "12px sans-serif";
+		this.outerCtx.textAlign = "center";
+		this.outerCtx.fillStyle = "white";
+		for (let label of this.labels) {
+			this.outerCtx.fillText(
+				label.text,
+				this.canvas.width / 2 + (label.x + 0.5 - this.centerX) * tileSize,
+				this.canvas.height / 2 + (label.y - this.centerY) * tileSize - 2
+			);
+		}
 	}
 
 	resize(width, height) {
@@ -32,14 +32,17 @@ function start(e) {
 			sage: {x: 1, y: 0},
 			worktable: {x: 6, y: 0},
 			altar: {x: 7, y: 0},
+			cache: {x: 7, y: 6},
 			grass1: {x: 0, y: 1, layer: "ground"},
 			grass2: {x: 1, y: 1, layer: "ground"},
 			grass3: {x: 2, y: 1, layer: "ground"},
 			dirt: {x: 3, y: 1, layer: "ground"},
+			tilled: {x: 3, y: 1, layer: "ground"},
 			rockmid: {x: 4, y: 1, border: "#222", layer: "base"},
 			" ": {x: 4, y: 1},
 			rock: {x: 5, y: 1, border: "#222", layer: "base"},
 			water: {x: 6, y: 1, border: "#004", layer: "base"},
+			ripples: {x: 3, y: 6},
 			moss: {x: 7, y: 1, layer: "ground"},
 			deadleaves: {x: 0, y: 2, layer: "ground"},
 			densegrass: {x: 1, y: 2, layer: "ground"},
@@ -47,20 +50,31 @@ function start(e) {
 			woodwall: {x: 3, y: 2, border: "#220", layer: "base"},
 			stonefloor: {x: 4, y: 2, layer: "base"},
 			rockfloor: {x: 5, y: 2, layer: "ground"},
+			sand: {x: 4, y: 6, layer: "ground"},
+			snow: {x: 5, y: 6, layer: "ground"},
+			ice: {x: 6, y: 6, layer: "ground"},
 			rush: {x: 0, y: 3},
+			weeds: {x: 1, y: 2},
 			pitcherplant: {x: 1, y: 3},
 			tree: {x: 2, y: 5, ho: true},
 			oldtree: {x: 3, y: 5},
 			oldtreetinder: {x: 4, y: 5, ho: true},
 			youngtree: {x: 1, y: 5},
 			sapling: {x: 0, y: 5},
+			pinetree: {x: 5, y: 5},
+			cactus: {x: 6, y: 5},
+			heather: {x: 7, y: 5},
 			shrub: {x: 6, y: 3},
 			bush: {x: 7, y: 3},
 			reed: {x: 2, y: 3},
 			gravel: {x: 5, y: 3},
+			channel: {x: 5, y: 3, layer: "ground"},
 			pebble: {x: 0, y: 6},
 			stone: {x: 1, y: 6},
 			stick: {x: 2, y: 6},
+			caveentrance: {x: 6, y: 2, layer: "main"},
+			caveexit: {x: 6, y: 2, layer: "main"},
+			copperore: {x: 5, y: 1, border: "#222", layer: "base"},
 		},
 		8,
 		fuzzTemplate
@@ -84,11 +98,23 @@ class Movement {
 			KeyD: 0,
 			ArrowRight: 0
 		};
+		this.directions = {
+			KeyW: "north",
+			ArrowUp: "north",
+			KeyS: "south",
+			ArrowDown: "south",
+			KeyA: "west",
+			ArrowLeft: "west",
+			KeyD: "east",
+			ArrowRight: "east"
+		};
+		this.facing = null;
 	}
 
 	keydown(code) {
 		if (this.keys[code] !== undefined) {
 			this.keys[code] = 1;
+			this.facing = this.directions[code];
 			return true;
 		} else {
 			return false;
@@ -128,6 +154,9 @@ class Client {
 		this.websocket = null;
 		this.delay = parseParameters().delay|0;
 		this.movement = new Movement();
+		this.pos = null;
+		this.biome = null;
+		this.creatures = new Map();
 	}
 	
 	start(){
@@ -135,7 +164,7 @@ class Client {
 		this.websocket = new WebSocket(this.host);
 		this.websocket.addEventListener("open", e => {
 			document.getElementById("game").hidden = false;
-			e.target.send(JSON.stringify({introduction: this.username}));
+			e.target.send(JSON.stringify({introduction: {name: this.username, sections: "chunks"}}));
 		});
 		let keymap = {
 			Period: {select: "next"},
@@ -158,6 +187,11 @@ class Client {
 				this.sendInput({movement: this.movement.movement()});
 				return;
 			}
+			if (e.code == "KeyE" || e.code == "Space") {
+				e.preventDefault();
+				this.sendInput({interact: this.movement.facing});
+				return;
+			}
 			let action = keymap[e.code];
 			if (action){
 				e.preventDefault();
@@ -236,15 +270,37 @@ class Client {
 			this.display.setViewArea(args.area);
 		} else if (type === "section") {
 			this.display.drawSection(args.area.w, args.area.h, args.area.x, args.area.y, args.field, args.mapping);
+		} else if (type === "chunks") {
+			let missing = this.display.drawChunks(args);
+			if (missing.length) {
+				this.websocket.send(JSON.stringify({redraw: missing}));
+			}
 		} else if (type === "changecells") {
 			this.display.changeTiles(args);
 		} else if (type === "dynamics") {
-			this.display.drawDynamics(args);
+			for (let id of args.remove) {
+				this.creatures.delete(id);
+			}
+			for (let creature of args.set) {
+				this.creatures.set(creature.i, creature);
+			}
+			this.display.drawDynamics(Array.from(this.creatures.values()));
 		} else if (type == "playerpos") {
 			this.display.setCenter(args[0], args[1]);
 			let x = ((args[0] * 100) | 0) / 100;
 			let y = ((args[1] * 100) | 0) / 100;
 			document.getElementById("coordinates").textContent = `${x}, ${y}`;
+			this.pos = args;
+			this.drawCompass();
+		} else if (type === "layer") {
+			this.display.setLayer(args);
+			if (args < 0) {
+				this.biome = null;
+				this.drawCompass();
+			}
+		} else if (type === "biome") {
+			this.biome = args;
+			this.drawCompass();
 		} else if (type === "inventory") {
 			this.setInventory(args[0], args[1]);
 		} else if (type === "messages") {
@@ -256,6 +312,31 @@ class Client {
 		}
 	}
 
+	drawCompass() {
+		let compass = document.getElementById("compass");
+		compass.hidden = !this.biome;
+		if (!this.biome || !this.pos) {
+			return;
+		}
+		compass.replaceChildren();
+		let current = document.createElement("li");
+		current.className = "compass-current";
+		current.textContent = this.biome.current;
+		compass.appendChild(current);
+		let arrows = ["→", "↘", "↓", "↙", "←", "↖", "↑", "↗"];
+		for (let [biome, core] of this.biome.nearest) {
+			if (biome === this.biome.current) {
+				continue;
+			}
+			let dx = core[0] - this.pos[0];
+			let dy = core[1] - this.pos[1];
+			let arrow = arrows[(Math.round(Math.atan2(dy, dx) / (Math.PI / 4)) + 8) % 8];
+			let row = document.createElement("li");
+			row.textContent = `${biome} ${Math.round(Math.hypot(dx, dy))} ${arrow}`;
+			compass.appendChild(row);
+		}
+	}
+
 	setInventory(items, selected) {
 		let table = document.getElementById("inventory");
 